@@ -0,0 +1,22 @@
+use std::ptr;
+use winutils::window::{dialog::{self, DialogProc, IDOK}, WindowHandle};
+
+/// The id of the dialog template. The example needs a resource script with a dialog
+/// of this id, linked into the executable with e.g. the `embed-resource` crate.
+const IDD_ABOUT: u16 = 101;
+
+struct About;
+
+impl DialogProc for About {}
+
+fn main() -> std::io::Result<()> {
+    let button = unsafe { dialog::show_dialog(ptr::null_mut(), IDD_ABOUT, WindowHandle::NULL, About)? };
+
+    if button == IDOK {
+        println!("Closed with OK");
+    } else {
+        println!("Closed with button {}", button);
+    }
+
+    Ok(())
+}
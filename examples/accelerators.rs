@@ -0,0 +1,40 @@
+use winutils::window::{self, AcceleratorTable, Window, Windowing, ACCEL, FCONTROL, FVIRTKEY, LPARAM, LRESULT, WPARAM};
+
+const QUIT_COMMAND: u16 = 100;
+const VK_Q: u16 = 0x51;
+
+struct App;
+
+impl Windowing for App {
+    fn on_command(&mut self, w_param: WPARAM, _l_param: LPARAM) -> Option<LRESULT> {
+        if window::loword(w_param) != QUIT_COMMAND {
+            return None;
+        }
+
+        println!("Ctrl+Q pressed, quitting");
+        window::quit(0);
+
+        Some(0)
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let accelerators = AcceleratorTable::create(&[ACCEL {
+        fVirt: FVIRTKEY | FCONTROL,
+        key: VK_Q,
+        cmd: QUIT_COMMAND,
+    }])?;
+
+    let mut window = Window::new(App);
+    window.register("AcceleratorWindow")?;
+
+    let h_wnd = window.create_window("AcceleratorWindow", "Press Ctrl+Q to quit", 400, 300);
+    if h_wnd.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    window::show(h_wnd);
+    window.run_with_accelerators(h_wnd, &accelerators);
+
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use winutils::window::{self, Window, Windowing, LPARAM, LRESULT, WPARAM};
+
+const BUTTON_ID: u16 = 1;
+
+#[derive(Default)]
+struct Counter {
+    clicks: u32,
+}
+
+impl Windowing for Counter {
+    fn on_command(&mut self, w_param: WPARAM, _l_param: LPARAM) -> Option<LRESULT> {
+        // The low word is the id of the control.
+        if window::loword(w_param) != BUTTON_ID {
+            return None;
+        }
+
+        self.clicks += 1;
+        println!("Clicked {} times", self.clicks);
+
+        Some(0)
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let mut window = Window::new(Counter::default());
+    window.register("ButtonClicks")?;
+
+    let h_wnd = window.create_window("ButtonClicks", "Click the button", 400, 300);
+    if h_wnd.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    window::create_button(h_wnd, BUTTON_ID, "Click me", 140, 110, 120, 40)?;
+
+    window::show(h_wnd);
+    window.run();
+
+    println!("The button was clicked {} times in total", window.data().clicks);
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use winutils::{
+    utils::{msgbox, MB_ICONINFORMATION},
+    window::{self, Window, Windowing, LPARAM, LRESULT, WPARAM},
+};
+
+const GREET_BUTTON: u16 = 1;
+
+struct App;
+
+impl Windowing for App {
+    fn on_command(&mut self, w_param: WPARAM, _l_param: LPARAM) -> Option<LRESULT> {
+        // The low word is the id of the control.
+        if window::loword(w_param) != GREET_BUTTON {
+            return None;
+        }
+
+        msgbox("Hello from a button!", Some("Controls"), MB_ICONINFORMATION);
+
+        Some(0)
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let mut window = Window::new(App);
+    window.register("ControlsWindow")?;
+
+    let h_wnd = window.create_window("ControlsWindow", "Controls", 400, 300);
+    if h_wnd.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    window::create_static(h_wnd, 0, "Your name:", 20, 20, 100, 20)?;
+    window::create_edit(h_wnd, 0, "", 120, 18, 240, 24)?;
+    window::create_button(h_wnd, GREET_BUTTON, "Greet", 140, 110, 120, 40)?;
+
+    window::show(h_wnd);
+    window.run();
+
+    Ok(())
+}
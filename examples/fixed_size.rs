@@ -0,0 +1,22 @@
+use winutils::window::{self, Window, WindowBuilder, Windowing};
+
+struct Fixed;
+
+impl Windowing for Fixed {}
+
+fn main() -> std::io::Result<()> {
+    let mut window = Window::new(Fixed);
+    window.register("FixedSizeWindow")?;
+
+    let h_wnd = window.create(
+        &WindowBuilder::new("FixedSizeWindow")
+            .title("Can't resize me")
+            .size(400, 300)
+            .resizable(false)
+    )?;
+
+    window::show(h_wnd);
+    window.run();
+
+    Ok(())
+}
@@ -0,0 +1,39 @@
+use winutils::window::{self, Hotkey, Window, Windowing, LPARAM, LRESULT, MOD_ALT, MOD_CONTROL, MOD_NOREPEAT, WPARAM};
+
+const HOTKEY_ID: i32 = 1;
+const VK_K: u32 = 0x4B;
+
+struct App {
+    presses: u32,
+}
+
+impl Windowing for App {
+    fn on_hotkey(&mut self, w_param: WPARAM, _l_param: LPARAM) -> Option<LRESULT> {
+        if w_param as i32 != HOTKEY_ID {
+            return None;
+        }
+
+        self.presses += 1;
+        println!("Ctrl+Alt+K pressed {} time(s)", self.presses);
+
+        Some(0)
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let mut window = Window::new(App { presses: 0 });
+    window.register("HotkeyWindow")?;
+
+    let h_wnd = window.create_window("HotkeyWindow", "Press Ctrl+Alt+K anywhere", 400, 300);
+    if h_wnd.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    // Unregistered when it's dropped at the end of main.
+    let _hotkey = Hotkey::register(h_wnd, HOTKEY_ID, MOD_CONTROL | MOD_ALT | MOD_NOREPEAT, VK_K)?;
+
+    window::show(h_wnd);
+    window.run();
+
+    Ok(())
+}
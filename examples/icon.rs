@@ -0,0 +1,26 @@
+use winutils::window::{self, Window, WindowBuilder, Windowing};
+
+struct WithIcon;
+
+impl Windowing for WithIcon {}
+
+fn main() -> std::io::Result<()> {
+    // Run with the path of an icon file, e.g. `cargo run --example icon -- app.ico`
+    let path = std::env::args().nth(1).unwrap_or_else(|| "app.ico".to_owned());
+    let icon = window::load_icon_file(&path)?;
+
+    let mut window = Window::new(WithIcon);
+    window.register_with_icon("IconWindow", icon)?;
+
+    let h_wnd = window.create(
+        &WindowBuilder::new("IconWindow")
+            .title("Custom icon")
+            .size(400, 300)
+            .icon_handle(icon)
+    )?;
+
+    window::show(h_wnd);
+    window.run();
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use winutils::window::{self, Timer, Window, Windowing, LRESULT};
+
+const QUIT_TIMER: usize = 1;
+
+struct App;
+
+impl Windowing for App {
+    fn on_timer(&mut self, id: usize) -> Option<LRESULT> {
+        if id != QUIT_TIMER {
+            return None;
+        }
+
+        println!("2 seconds passed, quitting");
+        window::quit(0);
+
+        Some(0)
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let mut window = Window::new(App);
+    window.register("QuitTimerWindow")?;
+
+    let h_wnd = window.create_window("QuitTimerWindow", "Closes after 2 seconds", 400, 300);
+    if h_wnd.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let _timer = Timer::set(h_wnd, QUIT_TIMER, 2000)?;
+
+    window::show(h_wnd);
+    let exit_code = window.run();
+    println!("The message loop ended with {}", exit_code);
+
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use winutils::window::{self, Window, Windowing, LRESULT};
+
+struct Resize;
+
+impl Windowing for Resize {
+    fn on_size(&mut self, width: i32, height: i32) -> Option<LRESULT> {
+        println!("Resized to {}x{}", width, height);
+        Some(0)
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let mut window = Window::new(Resize);
+    window.register("ResizeWindow")?;
+
+    let h_wnd = window.create_window("ResizeWindow", "Resize me", 400, 300);
+    if h_wnd.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    window::show(h_wnd);
+    window.run();
+
+    Ok(())
+}
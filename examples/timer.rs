@@ -0,0 +1,40 @@
+use winutils::window::{self, Timer, Window, WindowHandle, Windowing, LRESULT};
+
+const COUNTER_TIMER: usize = 1;
+
+struct App {
+    h_wnd: WindowHandle,
+    count: u32,
+}
+
+impl Windowing for App {
+    fn on_timer(&mut self, id: usize) -> Option<LRESULT> {
+        if id != COUNTER_TIMER {
+            return None;
+        }
+
+        self.count += 1;
+        window::set_window_text(self.h_wnd, &format!("Counter: {}", self.count)).ok();
+
+        Some(0)
+    }
+}
+
+fn main() -> std::io::Result<()> {
+    let mut window = Window::new(App { h_wnd: WindowHandle::NULL, count: 0 });
+    window.register("TimerWindow")?;
+
+    let h_wnd = window.create_window("TimerWindow", "Counter: 0", 400, 300);
+    if h_wnd.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+    window.data_mut().h_wnd = h_wnd;
+
+    // Stopped when it's dropped at the end of main.
+    let _timer = Timer::set(h_wnd, COUNTER_TIMER, 500)?;
+
+    window::show(h_wnd);
+    window.run();
+
+    Ok(())
+}
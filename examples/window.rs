@@ -0,0 +1,20 @@
+use winutils::window::{self, Window, Windowing};
+
+struct Blank;
+
+impl Windowing for Blank {}
+
+fn main() -> std::io::Result<()> {
+    let mut window = Window::new(Blank);
+    window.register("BlankWindow")?;
+
+    let h_wnd = window.create_window("BlankWindow", "Blank window", 400, 300);
+    if h_wnd.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    window::show(h_wnd);
+    window.run();
+
+    Ok(())
+}
@@ -0,0 +1,27 @@
+use winutils::window::{self, Window, Windowing};
+
+struct App;
+
+impl Windowing for App {}
+
+fn main() -> std::io::Result<()> {
+    let mut window = Window::new(App);
+    window.register("WindowTextWindow")?;
+
+    let h_wnd = window.create_window("WindowTextWindow", "Window text", 400, 300);
+    if h_wnd.is_null() {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    let edit = window::create_edit(h_wnd, 1, "", 20, 20, 340, 24)?;
+    window::set_window_text(edit, "Hello from set_window_text!")?;
+    println!("The edit field contains {:?}", window::get_window_text(edit)?.to_string());
+
+    window::set_window_text(h_wnd, "A new title")?;
+    println!("The title is {:?}", window::get_window_text(h_wnd)?.to_string());
+
+    window::show(h_wnd);
+    window.run();
+
+    Ok(())
+}
@@ -4,13 +4,111 @@ extern crate proc_macro;
 use ::std::{ffi::OsStr, iter, os::windows::prelude::OsStrExt};
 
 use proc_macro::TokenStream;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_macro_input, Ident, Lit, LitInt, LitStr, Macro, Token,
+};
+
+/// The arguments of [`wstr!`]: the text and an optional size.
+struct WstrArgs {
+    text: String,
+    size: Option<LitInt>,
+}
+
+impl Parse for WstrArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let text = parse_text(input)?;
+
+        let mut size = None;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            size = Some(input.parse()?);
+            input.parse::<Option<Token![,]>>()?;
+        }
+
+        Ok(Self { text, size })
+    }
+}
+
+/// The arguments of [`wstrlen!`]: the text and an optional `nul`.
+struct WstrlenArgs {
+    text: String,
+    with_nul: bool,
+}
+
+impl Parse for WstrlenArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let text = parse_text(input)?;
+
+        let mut with_nul = false;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            if ident != "nul" {
+                return Err(syn::Error::new_spanned(ident, "expected `nul`"));
+            }
+
+            with_nul = true;
+            input.parse::<Option<Token![,]>>()?;
+        }
+
+        Ok(Self { text, with_nul })
+    }
+}
+
+/// Parses a string literal or a `concat!` of literals into its value.
+fn parse_text(input: ParseStream) -> syn::Result<String> {
+    if input.peek(Ident) && input.peek2(Token![!]) {
+        let mac: Macro = input.parse()?;
+
+        if !mac.path.is_ident("concat") {
+            return Err(syn::Error::new_spanned(&mac.path, "only string literals and `concat!` are supported"));
+        }
+
+        return mac.parse_body_with(|body: ParseStream| {
+            let mut text = String::new();
+
+            while !body.is_empty() {
+                text.push_str(&parse_part(body)?);
+
+                if body.is_empty() {
+                    break;
+                }
+                body.parse::<Token![,]>()?;
+            }
+
+            Ok(text)
+        });
+    }
+
+    Ok(input.parse::<LitStr>()?.value())
+}
+
+/// Parses one argument of `concat!` the same way `concat!` does.
+fn parse_part(input: ParseStream) -> syn::Result<String> {
+    if input.peek(Ident) && input.peek2(Token![!]) {
+        return parse_text(input);
+    }
+
+    match input.parse()? {
+        Lit::Str(lit) => Ok(lit.value()),
+        Lit::Char(lit) => Ok(lit.value().to_string()),
+        Lit::Int(lit) => Ok(lit.base10_digits().to_owned()),
+        Lit::Float(lit) => Ok(lit.base10_digits().to_owned()),
+        Lit::Bool(lit) => Ok(lit.value.to_string()),
+        lit => Err(syn::Error::new_spanned(lit, "expected a literal")),
+    }
+}
 
 /// A macro to help with creating static 16-bit Unicode byte arrays.
 /// It's meant to be used with struct-declarations with the Windows API.
 ///
-/// The first argument is a string-slice and is always required.
+/// The first argument is always required and is either a string-literal or a `concat!`
+/// of literals (nested `concat!`'s works too). Other expressions, like the name of a
+/// `const`, can't be used, since their value isn't known to the macro. Escape sequences
+/// like `\n`, `\t` and `\u{...}` works the same way as in a normal Rust string.
 ///
 /// The second argument is optional and is the size that the array should be.
+/// It's a compile error if the string and its null-byte doesn't fit in the size,
+/// since the array wouldn't be null-terminated.
 ///
 /// # Example
 /// ```
@@ -23,20 +121,64 @@ use proc_macro::TokenStream;
 ///     font_name: wstr!("Segoe UI", 32),
 /// };
 /// ```
+///
+/// "Segoe UI Semibold" needs 18 units with the null-byte, so this fails to compile.
+/// ```compile_fail
+/// let font_name: [u16; 8] = proc_wstring::wstr!("Segoe UI Semibold", 8);
+/// ```
 #[proc_macro]
 pub fn wstr(item: TokenStream) -> TokenStream {
-    let input: String = item.to_string();
-    let args: Vec<&str> = input.split(",").collect();
+    let args = parse_macro_input!(item as WstrArgs);
 
-    let mut wstr: Vec<u16> = OsStr::new(&args[0][1..args[0].len() - 1])
+    let mut wstr: Vec<u16> = OsStr::new(&args.text)
         .encode_wide()
         .chain(iter::once(0))
         .collect();
 
-    if let Some(size) = args.get(1) {
-        let val: usize = size.trim().parse().unwrap();
+    if let Some(size) = args.size {
+        let val: usize = match size.base10_parse() {
+            Ok(val) => val,
+            Err(err) => return err.to_compile_error().into(),
+        };
+
+        if wstr.len() > val {
+            let msg = format!(
+                "the string needs a size of at least {} (including the null-byte), but the size is {}",
+                wstr.len(),
+                val
+            );
+            return syn::Error::new(size.span(), msg).to_compile_error().into();
+        }
+
         wstr.resize(val, 0_u16);
     }
 
     format!("{:?}", &wstr).parse().unwrap()
 }
+
+/// A macro that returns the amount of 16-bit Unicode units a string uses, as an `usize`.
+/// It's encoded the same way as [`wstr!`], so characters outside the BMP counts as 2.
+///
+/// The first argument is the same as for [`wstr!`]. If the second argument is `nul`,
+/// the null-byte is counted as well.
+///
+/// # Example
+/// ```
+/// use proc_wstring::{wstr, wstrlen};
+///
+/// struct TESTSTRUCTW { font_name: [u16; wstrlen!("Segoe UI", nul)] }
+///
+/// let font = TESTSTRUCTW {
+///     font_name: wstr!("Segoe UI"),
+/// };
+///
+/// assert_eq!(wstrlen!("Segoe UI"), 8);
+/// ```
+#[proc_macro]
+pub fn wstrlen(item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(item as WstrlenArgs);
+
+    let len = OsStr::new(&args.text).encode_wide().count() + args.with_nul as usize;
+
+    format!("{}usize", len).parse().unwrap()
+}
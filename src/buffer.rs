@@ -1,4 +1,44 @@
-use ::std::mem;
+use ::std::{mem, ptr, slice};
+
+/// Types that are valid for any bytes, so [`DynBuffer`] can turn its bytes into them.
+/// It's implemented for the integer and float types, and arrays of them.
+///
+/// # Safety
+/// Every bit pattern must be a valid value of the type, so it must not contain e.g. `bool`,
+/// `char`, references or `NonZero*` types. Structs must be `#[repr(C)]` and only contain
+/// fields, that are `Pod` themselves.
+///
+/// The type must not have any padding, since [`DynBuffer::write`] copies all of its bytes
+/// and padding is uninitialized. Add the padding as fields instead, e.g. `_pad: u16` after
+/// a `u16` that's followed by a `u32`.
+///
+/// ## Example
+/// ```
+/// use winutils::utils::{DynBuffer, Pod};
+///
+/// #[repr(C)]
+/// #[derive(Clone, Copy)]
+/// struct Header {
+///     size: u32,
+///     count: u32,
+/// }
+///
+/// unsafe impl Pod for Header {}
+///
+/// let buffer = DynBuffer::from_struct(Header { size: 8, count: 0 });
+/// assert_eq!(buffer.as_ref::<Header>().map(|header| header.size), Some(8));
+/// ```
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($t:ty),*) => {
+        $(unsafe impl Pod for $t {})*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
 
 /// A buffer type, that is is supposed to change size on use with a C-function.
 #[repr(transparent)]
@@ -22,20 +62,105 @@ impl DynBuffer {
         }
     }
 
+    /// Creates a [`DynBuffer`] containing the bytes of `value`. Set e.g. the `cbSize` field
+    /// first, then pass the buffer with [`as_dyn_ptr`](Self::as_dyn_ptr) and get the changed
+    /// struct back with [`into_struct`](Self::into_struct).
+    ///
+    /// `cbSize` structs often have padding at the end or between the fields. It must be
+    /// declared as fields in `T`, see [`Pod`].
+    pub fn from_struct<T: Pod>(value: T) -> Self {
+        let mut buffer = Self::with_capacity(mem::size_of::<T>());
+        buffer.write(value);
+        buffer
+    }
+
+    /// Copies a `T` out of the start of the buffer, or `None` if the buffer is too small.
+    pub fn into_struct<T: Pod>(&self) -> Option<T> {
+        self.read(0)
+    }
+
     /// Returns the number of elements of the buffer as T.
     pub fn len<T>(&self) -> usize {
         self.inner.len() / mem::size_of::<T>()
     }
 
+    /// Returns `true` if the buffer has no bytes.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// Returns the amount of bytes the buffer can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.inner.capacity()
+    }
+
+    /// Sets the length of the buffer to 0, but keeps the allocated memory.
+    pub fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    /// Returns the raw bytes of the buffer.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.inner
+    }
+
     pub fn as_dyn_ptr<T>(&mut self, count: usize) -> *mut T {
         self.inner.resize(mem::size_of::<T>() * count, 0);
         self.inner.as_mut_ptr().cast()
     }
 
-    pub fn as_ref<T>(&self) -> &T {
+    /// Returns the start of the buffer as a `&T`, or `None` if the buffer is too small
+    /// or not aligned for `T`.
+    pub fn as_ref<T: Pod>(&self) -> Option<&T> {
+        if self.inner.len() < mem::size_of::<T>() || !self.is_aligned::<T>() {
+            return None;
+        }
+
+        unsafe { Some(&*(self.inner.as_ptr() as *const T)) }
+    }
+
+    /// Returns the buffer as a slice of `T`. Bytes at the end, that doesn't fill a whole `T`
+    /// are not included. The slice is empty, if the buffer isn't aligned for `T`.
+    pub fn as_slice<T: Pod>(&self) -> &[T] {
+        if mem::size_of::<T>() == 0 || !self.is_aligned::<T>() {
+            return &[];
+        }
+
+        unsafe { slice::from_raw_parts(self.inner.as_ptr().cast(), self.len::<T>()) }
+    }
+
+    /// Appends the bytes of `value` to the end of the buffer. This is for building
+    /// variable-length structs, with a header followed by data.
+    pub fn write<T: Pod>(&mut self, value: T) {
+        let size = mem::size_of::<T>();
+        let len = self.inner.len();
+        self.inner.reserve(size);
+
+        // `Pod` types have no padding, so all of the copied bytes are initialized.
         unsafe {
-            mem::transmute(&self.inner)
+            ptr::copy_nonoverlapping(&value as *const T as *const u8, self.inner.as_mut_ptr().add(len), size);
+            self.inner.set_len(len + size);
         }
-    } 
+    }
+
+    /// Copies a `T` out of the buffer at the byte `offset`, or `None` if it doesn't fit.
+    /// The offset doesn't need to be aligned for `T`.
+    pub fn read<T: Pod>(&self, offset: usize) -> Option<T> {
+        let end = offset.checked_add(mem::size_of::<T>())?;
+        if end > self.inner.len() {
+            return None;
+        }
+
+        unsafe { Some(self.inner.as_ptr().add(offset).cast::<T>().read_unaligned()) }
+    }
+
+    fn is_aligned<T>(&self) -> bool {
+        (self.inner.as_ptr() as usize).is_multiple_of(mem::align_of::<T>())
+    }
 }
 
+impl Default for DynBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
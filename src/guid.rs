@@ -0,0 +1,150 @@
+use ::std::{fmt, io, str::FromStr};
+
+/// A globally unique identifier, used for e.g. CLSIDs, IIDs and KNOWNFOLDERIDs.
+/// It has the same layout as `GUID` in the Windows API.
+///
+/// ## Example
+/// ```
+/// use winutils::guid::GUID;
+///
+/// // {3EB685DB-65F9-4CF6-A03A-E3EF65729F3D}
+/// const ROAMING_APP_DATA: GUID = GUID::from_u128(0x3EB685DB_65F9_4CF6_A03A_E3EF65729F3D);
+/// const SHELL_LINK: GUID = winutils::guid!("{00021401-0000-0000-C000-000000000046}");
+/// ```
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct GUID {
+    pub data1: u32,
+    pub data2: u16,
+    pub data3: u16,
+    pub data4: [u8; 8],
+}
+
+impl GUID {
+    /// Creates a `GUID` from its parts.
+    pub const fn new(data1: u32, data2: u16, data3: u16, data4: [u8; 8]) -> Self {
+        Self { data1, data2, data3, data4 }
+    }
+
+    /// Creates a `GUID` from a number, written in the same order as the
+    /// `{xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx}` form.
+    pub const fn from_u128(value: u128) -> Self {
+        let bytes = value.to_be_bytes();
+
+        Self {
+            data1: (value >> 96) as u32,
+            data2: (value >> 80) as u16,
+            data3: (value >> 64) as u16,
+            data4: [
+                bytes[8], bytes[9], bytes[10], bytes[11],
+                bytes[12], bytes[13], bytes[14], bytes[15],
+            ],
+        }
+    }
+    /// Parses the `{xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx}` form, with or without the braces.
+    /// Returns `None` if `text` isn't in that form. It's a `const fn`, so [`guid!`] can
+    /// parse GUIDs at compile time.
+    pub const fn parse(text: &str) -> Option<Self> {
+        let bytes = text.as_bytes();
+
+        let (start, end) = if bytes.len() == 38 && bytes[0] == b'{' && bytes[37] == b'}' {
+            (1, 37)
+        } else if bytes.len() == 36 {
+            (0, 36)
+        } else {
+            return None;
+        };
+
+        let mut value: u128 = 0;
+        let mut i = start;
+
+        while i < end {
+            let c = bytes[i];
+
+            match i - start {
+                8 | 13 | 18 | 23 => if c != b'-' {
+                    return None;
+                },
+                _ => {
+                    let digit = match c {
+                        b'0'..=b'9' => c - b'0',
+                        b'a'..=b'f' => c - b'a' + 10,
+                        b'A'..=b'F' => c - b'A' + 10,
+                        _ => return None,
+                    };
+
+                    value = (value << 4) | digit as u128;
+                }
+            }
+
+            i += 1;
+        }
+
+        Some(Self::from_u128(value))
+    }
+}
+
+impl GUID {
+    /// Creates a new random `GUID` with `CoCreateGuid`.
+    pub fn new_random() -> io::Result<Self> {
+        let mut guid = Self::default();
+
+        unsafe {
+            let result = CoCreateGuid(&mut guid);
+            if result < 0 {
+                return Err(io::Error::from_raw_os_error(result));
+            }
+        }
+
+        Ok(guid)
+    }
+}
+
+/// Parses the same forms as [`GUID::parse`].
+impl FromStr for GUID {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Invalid GUID."))
+    }
+}
+
+/// Formats as `{xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx}` in uppercase, like the Windows API does.
+impl fmt::Display for GUID {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let d = &self.data4;
+
+        write!(
+            f,
+            "{{{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}}}",
+            self.data1, self.data2, self.data3, d[0], d[1], d[2], d[3], d[4], d[5], d[6], d[7]
+        )
+    }
+}
+
+/// Creates a [`GUID`](crate::guid::GUID) constant from a string literal in the
+/// `{xxxxxxxx-xxxx-xxxx-xxxx-xxxxxxxxxxxx}` form. An invalid GUID is a compile error.
+///
+/// ## Example
+/// ```
+/// use winutils::{guid, guid::GUID};
+///
+/// const IID_IUNKNOWN: GUID = guid!("00000000-0000-0000-C000-000000000046");
+/// assert_eq!(IID_IUNKNOWN.data4[0], 0xC0);
+/// ```
+#[macro_export]
+macro_rules! guid {
+    ($text:literal) => {{
+        const GUID: $crate::guid::GUID = match $crate::guid::GUID::parse($text) {
+            Some(guid) => guid,
+            None => panic!("Invalid GUID."),
+        };
+
+        GUID
+    }};
+}
+
+#[link(name = "Ole32")]
+extern "system" {
+    fn CoCreateGuid(pguid: *mut GUID) -> i32;
+}
@@ -1,19 +1,63 @@
 #![allow(dead_code)]
 
 use ::std::{ffi::c_void, io, ptr};
+use crate::guid::GUID;
 type HRESULT = i32;
 
+const CLSCTX_INPROC_SERVER: u32 = 0x1;
+const ERROR_ACCESS_DENIED: i32 = 5;
+const ERROR_INVALID_PARAMETER: i32 = 87;
+const CO_E_NOTINITIALIZED: i32 = 0x800401F0_u32 as i32;
+
+pub const COINIT_MULTI_THREADED: u32 = 0x0;
+pub const COINIT_APARTMENT_THREADED: u32 = 0x2;
+pub const COINIT_DISABLE_OLE1DDE: u32 = 0x4;
+pub const COINIT_SPEED_OVER_MEMORY: u32 = 0x8;
+
+/// How much the calls are authenticated for [`ComInit::init_security_ex`]. `DEFAULT` lets COM
+/// choose, `CONNECT` only checks it when connecting, and `PKT_PRIVACY` also encrypts the calls.
+pub const RPC_C_AUTHN_LEVEL_DEFAULT: u32 = 0;
+pub const RPC_C_AUTHN_LEVEL_NONE: u32 = 1;
+pub const RPC_C_AUTHN_LEVEL_CONNECT: u32 = 2;
+pub const RPC_C_AUTHN_LEVEL_CALL: u32 = 3;
+pub const RPC_C_AUTHN_LEVEL_PKT: u32 = 4;
+pub const RPC_C_AUTHN_LEVEL_PKT_INTEGRITY: u32 = 5;
+pub const RPC_C_AUTHN_LEVEL_PKT_PRIVACY: u32 = 6;
+
+/// How much a server can act as the client for [`ComInit::init_security_ex`]. `IDENTIFY` only
+/// lets it check who the client is, `IMPERSONATE` lets it access local resources as the client,
+/// and `DELEGATE` also lets it access other computers as the client.
+pub const RPC_C_IMP_LEVEL_DEFAULT: u32 = 0;
+pub const RPC_C_IMP_LEVEL_ANONYMOUS: u32 = 1;
+pub const RPC_C_IMP_LEVEL_IDENTIFY: u32 = 2;
+pub const RPC_C_IMP_LEVEL_IMPERSONATE: u32 = 3;
+pub const RPC_C_IMP_LEVEL_DELEGATE: u32 = 4;
+
+/// The `EOLE_AUTHENTICATION_CAPABILITIES` for [`ComInit::init_security_ex`].
+pub const EOAC_NONE: u32 = 0x0;
+pub const EOAC_STATIC_CLOAKING: u32 = 0x20;
+pub const EOAC_DYNAMIC_CLOAKING: u32 = 0x40;
+pub const EOAC_DISABLE_AAA: u32 = 0x1000;
+
+/// The error, when the security is already initialized for the process.
+pub const RPC_E_TOO_LATE: i32 = 0x80010119_u32 as i32;
+
 /// Struct for helping with COM initialization. this struct automaticly calls
 ///  `CoUninitialize` when the variable is dropped.
 pub struct ComInit;
 
+/// Struct for helping with OLE initialization, which is needed for e.g. drag and drop and
+/// the clipboard with OLE. This struct automaticly calls `OleUninitialize` when the variable
+/// is dropped.
+pub struct OleInit;
+
 /// Struct for helping with the Windows Runtime initialization. this struct automaticly calls
 ///  `RoUninitialize` when the variable is dropped.
 pub struct RoInit;
 
 impl ComInit {
     /// Initializes the COM library as single-threaded.
-    /// This function fails, if it's already initialized for the current thread
+    /// This function fails, if it's already initialized as multi-threaded for the current thread
     ///
     /// ## Example
     /// ```
@@ -26,18 +70,57 @@ impl ComInit {
     /// }
     /// ```
     pub fn init_sta() -> io::Result<Self> {
+        Self::init_ex(COINIT_APARTMENT_THREADED)
+    }
+
+    /// Initializes the COM library as multi-threaded.
+    /// This function fails, if it's already initialized as single-threaded for the current thread
+    ///
+    /// ## Example
+    /// ```
+    /// use winutils::init::ComInit;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let _com = ComInit::init_mta()?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn init_mta() -> io::Result<Self> {
+        Self::init_ex(COINIT_MULTI_THREADED)
+    }
+
+    /// Initializes the COM library with the `COINIT_` flags in `flags`. One of the apartment
+    /// models can be combined with the other flags.
+    ///
+    /// If COM is already initialized for the current thread with the same apartment model,
+    /// `CoInitializeEx` returns `S_FALSE` (1). This is a success, and the returned `ComInit`
+    /// still calls `CoUninitialize` to release the extra initialization, when it's dropped.
+    ///
+    /// ## Example
+    /// ```
+    /// use winutils::init::{ComInit, COINIT_APARTMENT_THREADED, COINIT_DISABLE_OLE1DDE};
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let _com = ComInit::init_ex(COINIT_APARTMENT_THREADED | COINIT_DISABLE_OLE1DDE)?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn init_ex(flags: u32) -> io::Result<Self> {
         #[cfg(feature = "winapi-crate")]
         unsafe {
-            let result = winapi::um::combaseapi::CoInitializeEx(ptr::null_mut(), 2);
-            if result != 0 {
+            let result = winapi::um::combaseapi::CoInitializeEx(ptr::null_mut(), flags);
+            if result < 0 {
                 return Err(io::Error::from_raw_os_error(result as i32));
             }
         }
 
         #[cfg(not(feature = "winapi-crate"))]
         unsafe {
-            let result = CoInitializeEx(ptr::null_mut(), 2);
-            if result != 0 {
+            let result = CoInitializeEx(ptr::null_mut(), flags);
+            // S_OK (0) and S_FALSE (1) both succeed, only negative HRESULT's are failures.
+            if result < 0 {
                 return Err(io::Error::from_raw_os_error(result as i32));
             }
         }
@@ -45,33 +128,78 @@ impl ComInit {
         Ok(Self)
     }
 
-    /// Initializes the COM library as multi-threaded.
-    /// This function fails, if it's already initialized for the current thread
+    /// Initializes the security of COM for the whole process with `CoInitializeSecurity`, using
+    /// the default authentication level and `RPC_C_IMP_LEVEL_IMPERSONATE`. This is what e.g. WMI
+    /// needs. It must be called after initializing COM, but before any interfaces are used.
     ///
     /// ## Example
-    /// ```
+    /// ```no_run
     /// use winutils::init::ComInit;
     ///
     /// fn main() -> std::io::Result<()> {
-    ///     let _com = ComInit::init_mta()?;
+    ///     let com = ComInit::init_mta()?;
+    ///     com.init_security()?;
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub fn init_mta() -> io::Result<Self> {
-        #[cfg(feature = "winapi-crate")]
+    pub fn init_security(&self) -> io::Result<()> {
+        self.init_security_ex(RPC_C_AUTHN_LEVEL_DEFAULT, RPC_C_IMP_LEVEL_IMPERSONATE, EOAC_NONE)
+    }
+
+    /// The same as [`init_security`](Self::init_security), but with the `RPC_C_AUTHN_LEVEL_`
+    /// value in `authn_level`, the `RPC_C_IMP_LEVEL_` value in `imp_level`, and the `EOAC_`
+    /// flags in `capabilities`.
+    ///
+    /// The authentication level is the least the process accepts as a server, and uses as a
+    /// client. The impersonation level is only used as a client. The default access
+    /// permissions and authentication services of the system are used.
+    ///
+    /// The security can only be initialized once per process. It fails with
+    /// [`RPC_E_TOO_LATE`], if it's already initialized by an earlier call, or implicitly
+    /// by COM, once an interface is marshalled.
+    pub fn init_security_ex(&self, authn_level: u32, imp_level: u32, capabilities: u32) -> io::Result<()> {
         unsafe {
-            let result = winapi::um::combaseapi::CoInitializeEx(ptr::null_mut(), 0);
-            if result != 0 {
-                return Err(io::Error::from_raw_os_error(result as i32));
+            let result = CoInitializeSecurity(
+                ptr::null_mut(),
+                -1,
+                ptr::null_mut(),
+                ptr::null_mut(),
+                authn_level,
+                imp_level,
+                ptr::null_mut(),
+                capabilities,
+                ptr::null_mut(),
+            );
+
+            if result < 0 {
+                return Err(io::Error::from_raw_os_error(result));
             }
         }
 
-        #[cfg(not(feature = "winapi-crate"))]
+        Ok(())
+    }
+}
+
+impl OleInit {
+    /// Initializes OLE and COM for the current thread. `OleInitialize` always uses the
+    /// single-threaded apartment, so it fails if COM is already initialized as multi-threaded.
+    ///
+    /// ## Example
+    /// ```
+    /// use winutils::init::OleInit;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let _ole = OleInit::init()?;
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn init() -> io::Result<Self> {
         unsafe {
-            let result = CoInitializeEx(ptr::null_mut(), 0);
-            if result != 0 {
-                return Err(io::Error::from_raw_os_error(result as i32));
+            let result = OleInitialize(ptr::null_mut());
+            if result < 0 {
+                return Err(io::Error::from_raw_os_error(result));
             }
         }
 
@@ -79,6 +207,167 @@ impl ComInit {
     }
 }
 
+/// The COM apartment of a thread. See [`current_apartment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Apartment {
+    /// COM is not initialized for the thread.
+    None,
+    /// Single-threaded, e.g. from [`ComInit::init_sta`] or [`OleInit`]. The main STA is also `Sta`.
+    Sta,
+    /// Multi-threaded, e.g. from [`ComInit::init_mta`], or implicitly because another thread
+    /// in the process has initialized it.
+    Mta,
+    /// Neutral, which is only used inside some COM objects.
+    Na,
+}
+
+/// Returns the COM apartment of the current thread with `CoGetApartmentType`. This can be used
+/// to check, if COM needs to be initialized, before calling e.g. [`ComInit::init_sta`].
+///
+/// ## Example
+/// ```
+/// use winutils::init::{current_apartment, Apartment, ComInit};
+///
+/// fn main() -> std::io::Result<()> {
+///     let _com = match current_apartment()? {
+///         Apartment::None => Some(ComInit::init_sta()?),
+///         _ => None,
+///     };
+///
+///     Ok(())
+/// }
+/// ```
+pub fn current_apartment() -> io::Result<Apartment> {
+    let mut apt_type = 0;
+    let mut qualifier = 0;
+
+    let result = unsafe { CoGetApartmentType(&mut apt_type, &mut qualifier) };
+    if result == CO_E_NOTINITIALIZED {
+        return Ok(Apartment::None);
+    } else if result < 0 {
+        return Err(io::Error::from_raw_os_error(result));
+    }
+
+    match apt_type {
+        // APTTYPE_STA and APTTYPE_MAINSTA.
+        0 | 3 => Ok(Apartment::Sta),
+        1 => Ok(Apartment::Mta),
+        2 => Ok(Apartment::Na),
+        _ => Err(io::Error::new(io::ErrorKind::InvalidData, "Unknown apartment type.")),
+    }
+}
+
+/// Creates an in-process COM object of the class `clsid`, and returns its `iid` interface.
+/// COM must be initialized for the current thread with e.g. [`ComInit`].
+///
+/// The caller owns the returned interface, and must call `Release` on it.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::{guid, init::{co_create_instance, ComInit}};
+/// use std::ffi::c_void;
+///
+/// fn main() -> std::io::Result<()> {
+///     let _com = ComInit::init_sta()?;
+///
+///     let shell_link: *mut c_void = co_create_instance(
+///         &guid!("{00021401-0000-0000-C000-000000000046}"),
+///         &guid!("{000214F9-0000-0000-C000-000000000046}"),
+///     )?;
+///
+///     Ok(())
+/// }
+/// ```
+pub fn co_create_instance<T>(clsid: &GUID, iid: &GUID) -> io::Result<*mut T> {
+    let mut object: *mut c_void = ptr::null_mut();
+
+    unsafe {
+        let result = CoCreateInstance(clsid, ptr::null_mut(), CLSCTX_INPROC_SERVER, iid, &mut object);
+        if result < 0 {
+            return Err(io::Error::from_raw_os_error(result));
+        }
+    }
+
+    Ok(object.cast())
+}
+
+/// The DPI awareness of the process. See [`set_dpi_aware`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(isize)]
+pub enum DpiAwareness {
+    /// Windows scales the windows as bitmaps, so they're blurry on high-DPI displays.
+    Unaware = -1,
+    /// The DPI of the primary monitor is used for every monitor.
+    SystemAware = -2,
+    /// The windows get `WM_DPICHANGED`, when they're moved to a monitor with another DPI.
+    PerMonitorAware = -3,
+    /// Like `PerMonitorAware`, but the non-client area and the dialogs are scaled too.
+    PerMonitorAwareV2 = -4,
+}
+
+/// Sets the DPI awareness of the process with `SetProcessDpiAwarenessContext`. If the system
+/// doesn't support `PerMonitorAwareV2`, it falls back to `PerMonitorAware`.
+///
+/// Before Windows 10 (1703) it falls back to `SetProcessDPIAware`, which can only make it
+/// system aware. Every other `context` fails with `InvalidInput` there.
+///
+/// The awareness can only be set once, before any windows are created. If it's already set,
+/// by a manifest or an earlier call, the error is ignored and `Ok` is returned.
+///
+/// Needs the `utils` feature, since the functions are loaded at runtime.
+///
+/// ## Example
+/// ```
+/// use winutils::init::{set_dpi_aware, DpiAwareness};
+///
+/// fn main() -> std::io::Result<()> {
+///     set_dpi_aware(DpiAwareness::PerMonitorAwareV2)?;
+///
+///     Ok(())
+/// }
+/// ```
+#[cfg(feature = "utils")]
+pub fn set_dpi_aware(context: DpiAwareness) -> io::Result<()> {
+    type SetProcessDpiAwarenessContext = unsafe extern "system" fn(value: *mut c_void) -> i32;
+    type SetProcessDPIAware = unsafe extern "system" fn() -> i32;
+
+    let user32 = crate::utils::Library::load("User32.dll")?;
+
+    unsafe {
+        if let Ok(set_context) = user32.try_load_func::<SetProcessDpiAwarenessContext>("SetProcessDpiAwarenessContext") {
+            let mut context = context;
+
+            while set_context(context as isize as *mut c_void) == 0 {
+                let error = io::Error::last_os_error();
+                match error.raw_os_error() {
+                    Some(ERROR_ACCESS_DENIED) => break,
+                    // Older versions of Windows 10 don't know the V2 context.
+                    Some(ERROR_INVALID_PARAMETER) if context == DpiAwareness::PerMonitorAwareV2 => {
+                        context = DpiAwareness::PerMonitorAware;
+                    },
+                    _ => return Err(error),
+                }
+            }
+
+            return Ok(());
+        }
+
+        if context != DpiAwareness::SystemAware {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Only system awareness is supported on this version of Windows.",
+            ));
+        }
+
+        let set_aware: SetProcessDPIAware = user32.try_load_func("SetProcessDPIAware")?;
+        if set_aware() == 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
 impl RoInit {
     /// Initializes the Windows Runtime as single-threaded.
     /// This function fails, if it's already initialized for the current thread
@@ -151,6 +440,14 @@ impl Drop for ComInit {
     }
 }
 
+impl Drop for OleInit {
+    fn drop(&mut self) {
+        unsafe {
+            OleUninitialize();
+        }
+    }
+}
+
 #[cfg(target_env = "msvc")]
 impl Drop for RoInit {
     fn drop(&mut self) {
@@ -167,6 +464,31 @@ extern "system" {
     fn CoUninitialize();
 }
 
+#[link(name = "Ole32")]
+extern "system" {
+    fn CoCreateInstance(
+        rclsid: *const GUID,
+        pUnkOuter: *mut c_void,
+        dwClsContext: u32,
+        riid: *const GUID,
+        ppv: *mut *mut c_void
+    ) -> HRESULT;
+    fn OleInitialize(pvReserved: *mut c_void) -> HRESULT;
+    fn CoGetApartmentType(pAptType: *mut i32, pAptQualifier: *mut i32) -> HRESULT;
+    fn CoInitializeSecurity(
+        pSecDesc: *mut c_void,
+        cAuthSvc: i32,
+        asAuthSvc: *mut c_void,
+        pReserved1: *mut c_void,
+        dwAuthnLevel: u32,
+        dwImpLevel: u32,
+        pAuthList: *mut c_void,
+        dwCapabilities: u32,
+        pReserved3: *mut c_void
+    ) -> HRESULT;
+    fn OleUninitialize();
+}
+
 #[cfg(target_env = "msvc")]
 #[link(name = "runtimeobject")]
 extern "system" {
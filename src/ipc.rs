@@ -0,0 +1,304 @@
+//! Module for communicating between processes with named pipes.
+//!
+//! Messages are sent as [`WideString`]'s in the format from [`WideString::to_wire`].
+
+use crate::wstring::{to_wide, WideString};
+use ::std::{ffi::c_void, io::{self, Read, Write}, ptr};
+
+type Handle = *mut c_void;
+
+const INVALID_HANDLE_VALUE: Handle = -1_isize as Handle;
+
+const PIPE_ACCESS_DUPLEX: u32 = 0x3;
+const PIPE_TYPE_BYTE: u32 = 0x0;
+const PIPE_WAIT: u32 = 0x0;
+const PIPE_UNLIMITED_INSTANCES: u32 = 255;
+const PIPE_BUFFER_SIZE: u32 = 4096;
+
+const GENERIC_READ: u32 = 0x80000000;
+const GENERIC_WRITE: u32 = 0x40000000;
+const OPEN_EXISTING: u32 = 3;
+
+/// The most UTF-16 code units a message can have, so a peer can't make the reader allocate
+/// an arbitrary amount of memory with the frame length.
+pub const MAX_MESSAGE_LEN: usize = 1 << 20;
+
+const ERROR_BROKEN_PIPE: i32 = 109;
+const ERROR_PIPE_CONNECTED: i32 = 535;
+
+/// The server end of a named pipe. The pipe is disconnected and closed when dropped.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::ipc::NamedPipeServer;
+///
+/// fn main() -> std::io::Result<()> {
+///     let mut server = NamedPipeServer::create("my_app")?;
+///     server.wait_connection()?;
+///
+///     let msg = server.read_message()?;
+///     println!("Client said: {}", msg);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug)]
+pub struct NamedPipeServer {
+    handle: Handle,
+    connected: bool,
+}
+
+/// The client end of a named pipe, connected to a [`NamedPipeServer`].
+/// The handle is closed when dropped.
+#[derive(Debug)]
+pub struct NamedPipeClient {
+    handle: Handle,
+}
+
+// Pipe handles are valid in the whole process, so they can be moved to another thread.
+unsafe impl Send for NamedPipeServer {}
+unsafe impl Send for NamedPipeClient {}
+
+impl NamedPipeServer {
+    /// Creates a new instance of the named pipe `name`. If `name` isn't a full pipe path,
+    /// it's created as `\\.\pipe\<name>`.
+    pub fn create(name: &str) -> io::Result<Self> {
+        let w_name = to_wide(&pipe_path(name));
+
+        unsafe {
+            let handle = CreateNamedPipeW(
+                w_name.as_ptr(),
+                PIPE_ACCESS_DUPLEX,
+                PIPE_TYPE_BYTE | PIPE_WAIT,
+                PIPE_UNLIMITED_INSTANCES,
+                PIPE_BUFFER_SIZE,
+                PIPE_BUFFER_SIZE,
+                0,
+                ptr::null_mut(),
+            );
+
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self { handle, connected: false })
+        }
+    }
+
+    /// Waits until a client connects to the pipe. Returns straight away,
+    /// if a client connected before the call.
+    pub fn wait_connection(&mut self) -> io::Result<()> {
+        unsafe {
+            if ConnectNamedPipe(self.handle, ptr::null_mut()) == 0 {
+                let error = io::Error::last_os_error();
+                if error.raw_os_error() != Some(ERROR_PIPE_CONNECTED) {
+                    return Err(error);
+                }
+            }
+        }
+
+        self.connected = true;
+        Ok(())
+    }
+
+    /// Reads the next message from the client. Blocks until the whole message is read.
+    /// Fails with `InvalidData`, if it's longer than [`MAX_MESSAGE_LEN`].
+    pub fn read_message(&self) -> io::Result<WideString> {
+        read_message(self.handle)
+    }
+
+    /// Writes a message to the client.
+    pub fn write_message(&self, msg: &WideString) -> io::Result<()> {
+        write_message(self.handle, msg)
+    }
+
+    /// Returns the raw handle of the pipe.
+    #[inline(always)]
+    pub fn handle(&self) -> *mut c_void {
+        self.handle
+    }
+}
+
+impl NamedPipeClient {
+    /// Connects to the named pipe `name`, that must already be created by a [`NamedPipeServer`].
+    /// If `name` isn't a full pipe path, it's opened as `\\.\pipe\<name>`.
+    pub fn connect(name: &str) -> io::Result<Self> {
+        let w_name = to_wide(&pipe_path(name));
+
+        unsafe {
+            let handle = CreateFileW(
+                w_name.as_ptr(),
+                GENERIC_READ | GENERIC_WRITE,
+                0,
+                ptr::null_mut(),
+                OPEN_EXISTING,
+                0,
+                ptr::null_mut(),
+            );
+
+            if handle == INVALID_HANDLE_VALUE {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self { handle })
+        }
+    }
+
+    /// Reads the next message from the server. Blocks until the whole message is read.
+    /// Fails with `InvalidData`, if it's longer than [`MAX_MESSAGE_LEN`].
+    pub fn read_message(&self) -> io::Result<WideString> {
+        read_message(self.handle)
+    }
+
+    /// Writes a message to the server.
+    pub fn write_message(&self, msg: &WideString) -> io::Result<()> {
+        write_message(self.handle, msg)
+    }
+
+    /// Returns the raw handle of the pipe.
+    #[inline(always)]
+    pub fn handle(&self) -> *mut c_void {
+        self.handle
+    }
+}
+
+impl Drop for NamedPipeServer {
+    fn drop(&mut self) {
+        unsafe {
+            if self.connected {
+                // Lets the client read what's left, before the data is discarded.
+                FlushFileBuffers(self.handle);
+                DisconnectNamedPipe(self.handle);
+            }
+
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+impl Drop for NamedPipeClient {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.handle);
+        }
+    }
+}
+
+/// Prefixes `name` with `\\.\pipe\`, unless it's already a full path.
+fn pipe_path(name: &str) -> String {
+    if name.starts_with(r"\\") {
+        name.to_owned()
+    } else {
+        format!(r"\\.\pipe\{}", name)
+    }
+}
+
+fn read_message(handle: Handle) -> io::Result<WideString> {
+    let mut pipe = PipeIo(handle);
+
+    let mut frame = vec![0_u8; 4];
+    pipe.read_exact(&mut frame)?;
+
+    let count = u32::from_le_bytes([frame[0], frame[1], frame[2], frame[3]]) as usize;
+    if count > MAX_MESSAGE_LEN {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "Message is too long."));
+    }
+
+    frame.resize(4 + count * 2, 0);
+    pipe.read_exact(&mut frame[4..])?;
+
+    WideString::from_wire(&frame).map(|(msg, _)| msg)
+}
+
+fn write_message(handle: Handle, msg: &WideString) -> io::Result<()> {
+    PipeIo(handle).write_all(&msg.to_wire())
+}
+
+/// Makes the `std::io` helpers like `read_exact` work on a raw pipe handle.
+struct PipeIo(Handle);
+
+impl Read for PipeIo {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut read = 0;
+
+        unsafe {
+            if ReadFile(self.0, buf.as_mut_ptr().cast(), buf.len() as u32, &mut read, ptr::null_mut()) == 0 {
+                let error = io::Error::last_os_error();
+
+                // The other end closed the pipe.
+                if error.raw_os_error() == Some(ERROR_BROKEN_PIPE) {
+                    return Ok(0);
+                }
+
+                return Err(error);
+            }
+        }
+
+        Ok(read as usize)
+    }
+}
+
+impl Write for PipeIo {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut written = 0;
+
+        unsafe {
+            if WriteFile(self.0, buf.as_ptr().cast(), buf.len() as u32, &mut written, ptr::null_mut()) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(written as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        unsafe {
+            if FlushFileBuffers(self.0) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[link(name = "Kernel32")]
+extern "system" {
+    fn CreateNamedPipeW(
+        lpName: *const u16,
+        dwOpenMode: u32,
+        dwPipeMode: u32,
+        nMaxInstances: u32,
+        nOutBufferSize: u32,
+        nInBufferSize: u32,
+        nDefaultTimeOut: u32,
+        lpSecurityAttributes: *mut c_void
+    ) -> Handle;
+    fn ConnectNamedPipe(hNamedPipe: Handle, lpOverlapped: *mut c_void) -> i32;
+    fn DisconnectNamedPipe(hNamedPipe: Handle) -> i32;
+    fn CreateFileW(
+        lpFileName: *const u16,
+        dwDesiredAccess: u32,
+        dwShareMode: u32,
+        lpSecurityAttributes: *mut c_void,
+        dwCreationDisposition: u32,
+        dwFlagsAndAttributes: u32,
+        hTemplateFile: Handle
+    ) -> Handle;
+    fn ReadFile(
+        hFile: Handle,
+        lpBuffer: *mut c_void,
+        nNumberOfBytesToRead: u32,
+        lpNumberOfBytesRead: *mut u32,
+        lpOverlapped: *mut c_void
+    ) -> i32;
+    fn WriteFile(
+        hFile: Handle,
+        lpBuffer: *const c_void,
+        nNumberOfBytesToWrite: u32,
+        lpNumberOfBytesWritten: *mut u32,
+        lpOverlapped: *mut c_void
+    ) -> i32;
+    fn FlushFileBuffers(hFile: Handle) -> i32;
+    fn CloseHandle(hObject: Handle) -> i32;
+}
@@ -6,11 +6,21 @@
 /// winapi-crate feature enabled (linking issue).
 #[cfg(feature = "init")] pub mod init;
 
+/// Module with the `GUID` type.
+pub mod guid;
+
 /// Module for working with unicode-strings.
 #[cfg(feature = "wstring")] pub mod wstring;
 
 /// Module for helping with Win32 GUI.
-// #[cfg(feature = "window")] pub mod window; // W.I.P.
+#[cfg(feature = "window")] pub mod window;
+#[cfg(feature = "window")] mod resource; // Used by window.rs.
+
+/// Module for communicating between processes with named pipes.
+#[cfg(feature = "ipc")] pub mod ipc;
+
+/// Module for reading and writing the registry.
+#[cfg(feature = "registry")] pub mod registry;
 
 /// Module with some utility functions.
 #[cfg(feature = "utils")] pub mod utils;
@@ -18,21 +28,5 @@
 #[cfg(feature = "utils")] mod unique; // Used by utils.rs.
 #[cfg(feature = "utils")] mod buffer; // Used by utils.rs.
 
-/// Converts a `&str` to a vector of UTF-16 bytes.
-#[cfg(any(
-    feature = "wstring", 
-    feature = "window", 
-    feature = "utils"
-))]
-fn get_wide_string(text: &str) -> Vec<u16> {
-    use ::std::ffi::OsStr;
-    use ::std::os::windows::ffi::OsStrExt;
-
-    OsStr::new(text)
-        .encode_wide()
-        .chain(std::iter::once(0))
-        .collect()
-}
-
 #[cfg(test)]
 mod tests;
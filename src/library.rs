@@ -1,8 +1,60 @@
-use ::std::{ffi::{c_void, CString}, io};
-use crate::get_wide_string;
+#![allow(non_snake_case)]
+
+use ::std::{ffi::{c_void, CStr, CString}, io, marker::PhantomData, mem, ops::Deref, ptr};
+use crate::wstring::to_wide;
+use crate::wstring::WideString;
 
 type FARPROC = Option<unsafe extern "system" fn() -> isize>;
 
+const ERROR_INSUFFICIENT_BUFFER: i32 = 122;
+
+const IMAGE_DOS_SIGNATURE: u16 = 0x5A4D; // "MZ"
+const IMAGE_NT_SIGNATURE: u32 = 0x4550; // "PE\0\0"
+const IMAGE_NT_OPTIONAL_HDR32_MAGIC: u16 = 0x10B;
+const IMAGE_NT_OPTIONAL_HDR64_MAGIC: u16 = 0x20B;
+const IMAGE_DIRECTORY_ENTRY_EXPORT: u32 = 0;
+
+const FORMAT_MESSAGE_ALLOCATE_BUFFER: u32 = 0x100;
+const FORMAT_MESSAGE_IGNORE_INSERTS: u32 = 0x200;
+const FORMAT_MESSAGE_FROM_SYSTEM: u32 = 0x1000;
+
+/// Flags for [`Library::load_with_flags`]. See more at
+/// https://docs.microsoft.com/en-us/windows/win32/api/libloaderapi/nf-libloaderapi-loadlibraryexw
+pub const DONT_RESOLVE_DLL_REFERENCES: u32 = 0x1;
+pub const LOAD_LIBRARY_AS_DATAFILE: u32 = 0x2;
+pub const LOAD_WITH_ALTERED_SEARCH_PATH: u32 = 0x8;
+pub const LOAD_IGNORE_CODE_AUTHZ_LEVEL: u32 = 0x10;
+pub const LOAD_LIBRARY_AS_IMAGE_RESOURCE: u32 = 0x20;
+pub const LOAD_LIBRARY_AS_DATAFILE_EXCLUSIVE: u32 = 0x40;
+pub const LOAD_LIBRARY_SEARCH_DLL_LOAD_DIR: u32 = 0x100;
+pub const LOAD_LIBRARY_SEARCH_APPLICATION_DIR: u32 = 0x200;
+pub const LOAD_LIBRARY_SEARCH_USER_DIRS: u32 = 0x400;
+pub const LOAD_LIBRARY_SEARCH_SYSTEM32: u32 = 0x800;
+pub const LOAD_LIBRARY_SEARCH_DEFAULT_DIRS: u32 = 0x1000;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IMAGE_DATA_DIRECTORY {
+    VirtualAddress: u32,
+    Size: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct IMAGE_EXPORT_DIRECTORY {
+    Characteristics: u32,
+    TimeDateStamp: u32,
+    MajorVersion: u16,
+    MinorVersion: u16,
+    Name: u32,
+    Base: u32,
+    NumberOfFunctions: u32,
+    NumberOfNames: u32,
+    AddressOfFunctions: u32,
+    AddressOfNames: u32,
+    AddressOfNameOrdinals: u32,
+}
+
 /// Struct for helping with loading external Libraries (dll).
 /// The Library is automaticly unloaded when dropped, Unlees a static lib is loaded 
 /// (can check with the [`lib_type`](`Self::lib_type`)).
@@ -15,6 +67,13 @@ pub struct Library {
     lib_type: LibType,
 }
 
+// A module handle is valid in the whole process and not tied to the thread that loaded
+// it, so a `Library` can be moved to another thread and freed there.
+//
+// It's not `Sync`, since `free_lib` only takes `&self`. If it was shared, one thread could
+// unload the library while another thread is calling a function from it.
+unsafe impl Send for Library {}
+
 /// Library types used by [`Library`]. Static libraries will not be unloaded on [`Drop`].
 #[derive(Debug, Clone, Copy)]
 pub enum LibType {
@@ -25,9 +84,11 @@ pub enum LibType {
 impl Library {
     /// Loads a dll file, from the system defined in `path`.
     /// It returns an [`std::io::Result`], based on if it worked.
+    /// The error keeps the code of the system, e.g. 126 (`ERROR_MOD_NOT_FOUND`), and shows
+    /// the same message as [`error_message`].
     pub fn load(path: &str) -> io::Result<Self> {
         unsafe {
-            let w_path = get_wide_string(path);
+            let w_path = to_wide(path);
             let handle = LoadLibraryW(w_path.as_ptr());
 
             if handle.is_null() {
@@ -41,6 +102,25 @@ impl Library {
         }
     }
 
+    /// Loads a dll file with `LoadLibraryExW`. The `flags` are the constants starting with
+    /// `LOAD_`, which e.g. can load it as a data file without running its `DllMain`, or
+    /// change where it's searched for. The library is still unloaded on [`Drop`].
+    pub fn load_with_flags(path: &str, flags: u32) -> io::Result<Self> {
+        unsafe {
+            let w_path = to_wide(path);
+            let handle = LoadLibraryExW(w_path.as_ptr(), ptr::null_mut(), flags);
+
+            if handle.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self {
+                handle,
+                lib_type: LibType::Dynamic
+            })
+        }
+    }
+
     /// Returns a [`Library`] from a raw handle. You should wheater not, it's a static 
     /// library or dynamic.
     pub fn from_handle(handle: *mut c_void, dynamic: bool) -> io::Result<Self> {
@@ -58,11 +138,15 @@ impl Library {
     /// Nothing happens, when this gets dropped.
     pub fn get_static_lib(path: &str) -> io::Result<Self> {
         unsafe {
-            let w_path = get_wide_string(path);
+            if path.is_empty() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "Not a lib name."));
+            }
+
+            let w_path = to_wide(path);
             let handle = GetModuleHandleW(w_path.as_ptr());
 
-            if handle.is_null() || path.len() == 0 {
-                return Err(io::Error::new(io::ErrorKind::InvalidInput, "Not a lib name."));
+            if handle.is_null() {
+                return Err(io::Error::last_os_error());
             }
 
             Ok(Self {
@@ -72,6 +156,17 @@ impl Library {
         }
     }
 
+    /// Returns whether the module `name` is already loaded in the process. Unlike
+    /// [`get_static_lib`](Self::get_static_lib), this only checks and doesn't fail.
+    pub fn is_loaded(name: &str) -> bool {
+        if name.is_empty() {
+            return false;
+        }
+
+        let w_name = to_wide(name);
+        unsafe { !GetModuleHandleW(w_name.as_ptr()).is_null() }
+    }
+
     /// Returns the [`LibType`] from `&self`.
     pub fn lib_type(&self) -> LibType {
         self.lib_type
@@ -85,7 +180,7 @@ impl Library {
     #[inline(always)]
     pub const fn empty() -> Self {
         Self {
-            handle: 0 as *mut c_void,
+            handle: ptr::null_mut(),
             lib_type: LibType::Static,
         }
     }
@@ -96,6 +191,79 @@ impl Library {
         self.handle
     }
 
+    /// Returns the full path of the file, the library was loaded from.
+    /// Fails if it's an [`empty`](Self::empty) library.
+    pub fn path(&self) -> io::Result<WideString> {
+        if self.handle.is_null() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Library is not loaded."));
+        }
+
+        module_file_name(self.handle)
+    }
+
+    /// Returns the names of the functions the library exports, by reading the export
+    /// directory of the loaded image. Functions only exported by ordinal are not included,
+    /// and a library without exports returns an empty vector.
+    ///
+    /// Fails for an [`empty`](Self::empty) library, or one loaded as a data file.
+    pub fn functions(&self) -> io::Result<Vec<String>> {
+        if self.handle.is_null() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Library is not loaded."));
+        }
+
+        // Libraries loaded with `LOAD_LIBRARY_AS_DATAFILE` or `LOAD_LIBRARY_AS_IMAGE_RESOURCE`
+        // have the low bits set, and are not mapped as an image.
+        if self.handle as usize & 0b11 != 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "Library is loaded as a data file."));
+        }
+
+        unsafe {
+            let base = self.handle as *const u8;
+            if base.cast::<u16>().read_unaligned() != IMAGE_DOS_SIGNATURE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a valid image."));
+            }
+
+            let nt_headers = base.offset(base.add(0x3C).cast::<i32>().read_unaligned() as isize);
+            if nt_headers.cast::<u32>().read_unaligned() != IMAGE_NT_SIGNATURE {
+                return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a valid image."));
+            }
+
+            // The optional header is after the signature and the file header. Where the data
+            // directories start depends on, if it's a 32 or 64-bit image.
+            let optional_header = nt_headers.add(24);
+            let (count_offset, directories_offset) = match optional_header.cast::<u16>().read_unaligned() {
+                IMAGE_NT_OPTIONAL_HDR32_MAGIC => (92, 96),
+                IMAGE_NT_OPTIONAL_HDR64_MAGIC => (108, 112),
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "Not a valid image.")),
+            };
+
+            let count = optional_header.add(count_offset).cast::<u32>().read_unaligned();
+            // The export directory is the first one.
+            if count == 0 {
+                return Ok(Vec::new());
+            }
+
+            let directory = optional_header
+                .add(directories_offset)
+                .cast::<IMAGE_DATA_DIRECTORY>()
+                .add(IMAGE_DIRECTORY_ENTRY_EXPORT as usize)
+                .read_unaligned();
+            if directory.VirtualAddress == 0 || directory.Size == 0 {
+                return Ok(Vec::new());
+            }
+
+            let exports = base.add(directory.VirtualAddress as usize).cast::<IMAGE_EXPORT_DIRECTORY>().read_unaligned();
+            let names = base.add(exports.AddressOfNames as usize).cast::<u32>();
+
+            Ok((0..exports.NumberOfNames as usize)
+                .map(|i| {
+                    let name = base.add(names.add(i).read_unaligned() as usize);
+                    CStr::from_ptr(name.cast()).to_string_lossy().into_owned()
+                })
+                .collect())
+        }
+    }
+
     /// Unloads the library without dropping the struct.
     /// Only use this, if your variable does not go out 
     /// of scope. If the library is static, nothing will happen.
@@ -123,6 +291,7 @@ impl Library {
 
     /// Retrieves the address of a given function name and wraps it in a [`FnWrapper`] struct.
     /// To use the function, you must [`unwrap`] / [`match`] it before using it.
+    /// Use [`try_load_func`](Self::try_load_func) to get the reason, if it fails.
     pub fn load_func<F: Sized>(&self, name: &str) -> FnWrapper<F> {
         unsafe {
             match CString::new(name) {
@@ -139,8 +308,61 @@ impl Library {
         }
     }
 
+    /// Retrieves the address of a function, that's exported with `ordinal`, and wraps it
+    /// in a [`FnWrapper`] struct. This is for functions, that are exported without a name.
+    ///
+    /// `F` must be a function pointer, so `Option<F>` has the size of the address. Other
+    /// types fail to compile.
+    pub fn load_func_ordinal<F: Sized>(&self, ordinal: u16) -> FnWrapper<F> {
+        const { assert!(mem::size_of::<Option<F>>() == mem::size_of::<FARPROC>(), "F must be a function pointer.") };
+
+        unsafe {
+            // The same as MAKEINTRESOURCE in C.
+            let proc = GetProcAddress(self.handle, ordinal as usize as *const u8);
+            let ref_proc: *const FARPROC = &proc;
+
+            FnWrapper(ref_proc.cast::<Option<F>>().read())
+        }
+    }
+
+    /// The same as [`load_func`], but returns the function-pointer directly, or an
+    /// error describing why it couldn't be found.
+    pub fn try_load_func<F: Sized>(&self, name: &str) -> io::Result<F> {
+        let cname = CString::new(name)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Function name contains a null-byte."))?;
+
+        unsafe {
+            let proc = GetProcAddress(self.handle, cname.as_bytes_with_nul().as_ptr());
+            if proc.is_none() {
+                return Err(io::Error::last_os_error());
+            }
+
+            let ref_proc: *const FARPROC = &proc;
+            Ok(ref_proc.cast::<Option<F>>().read().unwrap())
+        }
+    }
+
+    /// The same as [`try_load_func`](Self::try_load_func), but the function-pointer borrows the
+    /// library, so it can't be called after the library is dropped and unloaded.
+    ///
+    /// `F` must be a function pointer, so `Option<F>` has the size of the address. Other
+    /// types fail to compile.
+    pub fn load_func_ref<F: Copy>(&self, name: &str) -> io::Result<BorrowedFn<'_, F>> {
+        const { assert!(mem::size_of::<Option<F>>() == mem::size_of::<FARPROC>(), "F must be a function pointer.") };
+
+        Ok(BorrowedFn {
+            func: self.try_load_func(name)?,
+            _library: PhantomData,
+        })
+    }
+
     /// A faster and unsafe version [`load_func`]. This function will panic if the 
     /// function name is invalid or doesn't exist.
+    ///
+    /// # Safety
+    /// `F` must be a function pointer type, e.g. `extern "system" fn(i32) -> i32`, with the
+    /// same signature as the exported function. The function must not be called after the
+    /// library is dropped and unloaded.
     pub unsafe fn unsafe_func<F: Sized>(&self, name: &str) -> F {
         let cname = CString::new(name).unwrap_or_default();
         let proc = GetProcAddress(self.handle, cname.as_bytes_with_nul().as_ptr());
@@ -200,12 +422,250 @@ impl<F> FnWrapper<F> {
     pub fn unwrap(self) -> F {
         self.0.unwrap()
     }
+
+    /// Consumes the `FnWrapper` and unwraps the function-pointer underneath.
+    /// Panics with `msg`, if the function wasn't found.
+    ///
+    /// This is the same as `Option::expect`.
+    pub fn expect(self, msg: &str) -> F {
+        self.0.expect(msg)
+    }
+
+    /// Returns a reference to the function-pointer, without consuming the `FnWrapper`.
+    pub fn as_ref(&self) -> Option<&F> {
+        self.0.as_ref()
+    }
+
+    /// Returns a copy of the function-pointer, so the `FnWrapper` can be used again.
+    pub fn get(&self) -> Option<F>
+    where
+        F: Copy,
+    {
+        self.0
+    }
+}
+
+/// Resolves a function from a [`Library`](crate::utils::Library) with a C-style signature, and
+/// returns a [`FnWrapper`](crate::utils::FnWrapper) with the matching `extern "system" fn` type.
+/// The function name is used as the name of the export.
+///
+/// ## Example
+/// ```no_run
+/// use std::{ptr, ffi::c_void};
+/// use winutils::{symbol, utils::Library, wstring::WideString};
+///
+/// let user32 = Library::load("User32.dll").unwrap();
+/// let msgbox = symbol!(user32, fn MessageBoxW(*const c_void, *const u16, *const u16, u32) -> i32).unwrap();
+///
+/// let msg = WideString::from("Hello from a dynamic library loader!");
+/// msgbox(ptr::null(), msg.ptr(), ptr::null(), 0);
+/// ```
+#[macro_export]
+macro_rules! symbol {
+    ($lib:expr, fn $name:ident($($arg:ty),* $(,)?) $(-> $ret:ty)?) => {
+        $lib.load_func::<extern "system" fn($($arg),*) $(-> $ret)?>(stringify!($name))
+    };
+}
+
+/// Adds `path` to the directories, that libraries are searched in with `AddDllDirectory`.
+/// It's added for the whole process, until the returned [`SearchDirectory`] is dropped.
+///
+/// It's only searched, when the library is loaded with [`Library::load_with_flags`] and
+/// `LOAD_LIBRARY_SEARCH_USER_DIRS` or `LOAD_LIBRARY_SEARCH_DEFAULT_DIRS`. Use
+/// [`set_dll_directory`] for [`Library::load`] instead.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::utils::{add_search_directory, Library, LOAD_LIBRARY_SEARCH_DEFAULT_DIRS};
+///
+/// let _plugins = add_search_directory("C:\\MyApp\\plugins").unwrap();
+/// let plugin = Library::load_with_flags("plugin.dll", LOAD_LIBRARY_SEARCH_DEFAULT_DIRS).unwrap();
+/// ```
+pub fn add_search_directory(path: &str) -> io::Result<SearchDirectory> {
+    let w_path = to_wide(path);
+
+    unsafe {
+        let cookie = AddDllDirectory(w_path.as_ptr());
+        if cookie.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(SearchDirectory { cookie })
+    }
+}
+
+/// A directory added with [`add_search_directory`]. It's removed from the search path with
+/// `RemoveDllDirectory`, when dropped. Use `mem::forget` to keep it until the process exits.
+#[derive(Debug)]
+#[must_use = "the directory is removed again, when it's dropped"]
+pub struct SearchDirectory {
+    cookie: *mut c_void,
+}
+
+// The directory is added for the whole process, so it can be removed from any thread.
+unsafe impl Send for SearchDirectory {}
+
+impl SearchDirectory {
+    /// Removes the directory from the search path, like dropping it, but returns the error.
+    pub fn remove(self) -> io::Result<()> {
+        let cookie = self.cookie;
+        mem::forget(self);
+
+        unsafe {
+            if RemoveDllDirectory(cookie) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for SearchDirectory {
+    fn drop(&mut self) {
+        unsafe {
+            RemoveDllDirectory(self.cookie);
+        }
+    }
+}
+
+/// Sets the one extra directory, that [`Library::load`] searches in after the directory of
+/// the executable, with `SetDllDirectoryW`. It replaces the directory of an earlier call for
+/// the whole process, and `None` removes it again.
+pub fn set_dll_directory(path: Option<&str>) -> io::Result<()> {
+    let w_path = path.map(to_wide);
+
+    unsafe {
+        let ptr = w_path.as_ref().map_or(ptr::null(), |path| path.as_ptr());
+        if SetDllDirectoryW(ptr) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// A function-pointer from [`Library::load_func_ref`], that borrows the [`Library`]. It
+/// derefs to the function-pointer, so it can be called directly.
+///
+/// Copying the function-pointer out with `*` bypasses the borrow, so only do that if the
+/// library outlives it.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::utils::Library;
+///
+/// type GetTickCountProc = extern "system" fn() -> u32;
+///
+/// let kernel32 = Library::load("Kernel32.dll").unwrap();
+/// let get_tick_count = kernel32.load_func_ref::<GetTickCountProc>("GetTickCount").unwrap();
+/// println!("{}", get_tick_count());
+/// ```
+///
+/// It can't be used after the library is dropped.
+/// ```compile_fail
+/// use winutils::utils::Library;
+///
+/// type GetTickCountProc = extern "system" fn() -> u32;
+///
+/// let kernel32 = Library::load("Kernel32.dll").unwrap();
+/// let get_tick_count = kernel32.load_func_ref::<GetTickCountProc>("GetTickCount").unwrap();
+///
+/// drop(kernel32);
+/// println!("{}", get_tick_count());
+/// ```
+#[derive(Clone, Copy)]
+pub struct BorrowedFn<'lib, F> {
+    func: F,
+    _library: PhantomData<&'lib Library>,
+}
+
+impl<F> Deref for BorrowedFn<'_, F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.func
+    }
+}
+
+/// Returns the path of the module `handle`, or the executable if it's null.
+/// The buffer is grown until the path fits.
+pub(crate) fn module_file_name(handle: *mut c_void) -> io::Result<WideString> {
+    let mut buffer = WideString::with_size(260);
+
+    loop {
+        let size = buffer.bytes.len() as u32;
+        let len = unsafe { GetModuleFileNameW(handle, buffer.mut_ptr(), size) };
+
+        if len == 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        // The path is truncated, if it fills the whole buffer.
+        let error = io::Error::last_os_error();
+        if len < size || error.raw_os_error() != Some(ERROR_INSUFFICIENT_BUFFER) {
+            buffer.bytes.truncate(len as usize);
+            buffer.bytes.push(0);
+
+            return Ok(buffer);
+        }
+
+        buffer = WideString::with_size(size as usize * 2);
+    }
+}
+
+/// Returns the system message for a Win32 error code, without the trailing line break.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::utils::error_message;
+///
+/// // ERROR_FILE_NOT_FOUND
+/// println!("{}", error_message(2));
+/// ```
+pub fn error_message(code: u32) -> String {
+    unsafe {
+        let mut buffer: *mut u16 = ptr::null_mut();
+        let len = FormatMessageW(
+            FORMAT_MESSAGE_ALLOCATE_BUFFER | FORMAT_MESSAGE_FROM_SYSTEM | FORMAT_MESSAGE_IGNORE_INSERTS,
+            ptr::null(),
+            code,
+            0,
+            &mut buffer as *mut *mut u16 as *mut u16,
+            0,
+            ptr::null_mut()
+        );
+
+        if len == 0 || buffer.is_null() {
+            return format!("Unknown error {}", code);
+        }
+
+        let message = String::from_utf16_lossy(std::slice::from_raw_parts(buffer, len as usize));
+        LocalFree(buffer.cast());
+
+        message.trim_end().to_owned()
+    }
 }
 
 #[link(name = "Kernel32")]
 extern "system" {
+    fn FormatMessageW(
+        dwFlags: u32,
+        lpSource: *const c_void,
+        dwMessageId: u32,
+        dwLanguageId: u32,
+        lpBuffer: *mut u16,
+        nSize: u32,
+        Arguments: *mut c_void
+    ) -> u32;
+    fn LocalFree(hMem: *mut c_void) -> *mut c_void;
     fn LoadLibraryW(lpLibFileName: *const u16) -> *mut c_void;
+    fn LoadLibraryExW(lpLibFileName: *const u16, hFile: *mut c_void, dwFlags: u32) -> *mut c_void;
     fn FreeLibrary(hLibModule: *mut c_void) -> i32;
     fn GetProcAddress(hModule: *mut c_void, lpProcName: *const u8) -> FARPROC;
     fn GetModuleHandleW(lpModuleName: *const u16) -> *mut c_void;
+    fn GetModuleFileNameW(hModule: *mut c_void, lpFilename: *mut u16, nSize: u32) -> u32;
+    fn AddDllDirectory(NewDirectory: *const u16) -> *mut c_void;
+    fn RemoveDllDirectory(Cookie: *mut c_void) -> i32;
+    fn SetDllDirectoryW(lpPathName: *const u16) -> i32;
 }
\ No newline at end of file
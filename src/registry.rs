@@ -0,0 +1,254 @@
+//! Module for reading and writing values in the registry.
+//!
+//! ## Example
+//! ```no_run
+//! use winutils::registry::{self, HKEY_CURRENT_USER};
+//!
+//! fn main() -> std::io::Result<()> {
+//!     let temp = registry::read_string(HKEY_CURRENT_USER, "Environment", "TEMP")?;
+//!     println!("TEMP is {}", temp);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use crate::wstring::{to_wide, WideString};
+use ::std::{ffi::c_void, io, mem, ptr};
+
+type Hkey = *mut c_void;
+
+/// One of the predefined keys, that the other keys are opened under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Hive {
+    ClassesRoot,
+    CurrentUser,
+    LocalMachine,
+    Users,
+    CurrentConfig,
+}
+
+pub const HKEY_CLASSES_ROOT: Hive = Hive::ClassesRoot;
+pub const HKEY_CURRENT_USER: Hive = Hive::CurrentUser;
+pub const HKEY_LOCAL_MACHINE: Hive = Hive::LocalMachine;
+pub const HKEY_USERS: Hive = Hive::Users;
+pub const HKEY_CURRENT_CONFIG: Hive = Hive::CurrentConfig;
+
+const KEY_QUERY_VALUE: u32 = 0x1;
+const KEY_SET_VALUE: u32 = 0x2;
+const KEY_CREATE_SUB_KEY: u32 = 0x4;
+
+const REG_OPTION_NON_VOLATILE: u32 = 0x0;
+
+const REG_SZ: u32 = 1;
+const REG_EXPAND_SZ: u32 = 2;
+const REG_DWORD: u32 = 4;
+
+/// Reads the `REG_SZ` or `REG_EXPAND_SZ` value `value` of the key `hive\subkey`. The variables
+/// in a `REG_EXPAND_SZ` aren't expanded, so it's e.g. `%USERPROFILE%\AppData\Local\Temp`.
+pub fn read_string(hive: Hive, subkey: &str, value: &str) -> io::Result<WideString> {
+    let key = Key::open(hive, subkey, KEY_QUERY_VALUE)?;
+    let w_value = to_wide(value);
+
+    let mut kind = 0;
+    let mut size = 0;
+    key.query(&w_value, &mut kind, ptr::null_mut(), &mut size)?;
+
+    if kind != REG_SZ && kind != REG_EXPAND_SZ {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "The value isn't a string."));
+    }
+
+    // The stored string doesn't have to be null terminated, so there's room for one more.
+    let mut buffer = vec![0_u16; size as usize / 2 + 1];
+    size = (buffer.len() * 2) as u32;
+    key.query(&w_value, &mut kind, buffer.as_mut_ptr().cast(), &mut size)?;
+
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    buffer.truncate(len);
+
+    Ok(WideString::from(buffer.as_slice()))
+}
+
+/// Reads the `REG_DWORD` value `value` of the key `hive\subkey`.
+pub fn read_u32(hive: Hive, subkey: &str, value: &str) -> io::Result<u32> {
+    let key = Key::open(hive, subkey, KEY_QUERY_VALUE)?;
+    let w_value = to_wide(value);
+
+    let mut kind = 0;
+    let mut size = 0;
+    key.query(&w_value, &mut kind, ptr::null_mut(), &mut size)?;
+
+    if kind != REG_DWORD {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "The value isn't a DWORD."));
+    }
+
+    let mut data = 0_u32;
+    size = mem::size_of::<u32>() as u32;
+    key.query(&w_value, &mut kind, &mut data as *mut u32 as *mut u8, &mut size)?;
+
+    Ok(data)
+}
+
+/// Writes `data` as the `REG_SZ` value `value` of the key `hive\subkey`.
+/// The key is created, if it doesn't exist.
+pub fn write_string(hive: Hive, subkey: &str, value: &str, data: &str) -> io::Result<()> {
+    let w_data = to_wide(data);
+
+    // The size is in bytes, and includes the null terminator.
+    let bytes = unsafe { ::std::slice::from_raw_parts(w_data.as_ptr() as *const u8, w_data.len() * 2) };
+    Key::create(hive, subkey)?.set(value, REG_SZ, bytes)
+}
+
+/// Writes `data` as the `REG_DWORD` value `value` of the key `hive\subkey`.
+/// The key is created, if it doesn't exist.
+pub fn write_u32(hive: Hive, subkey: &str, value: &str, data: u32) -> io::Result<()> {
+    Key::create(hive, subkey)?.set(value, REG_DWORD, &data.to_ne_bytes())
+}
+
+/// Deletes the value `value` of the key `hive\subkey`.
+pub fn delete_value(hive: Hive, subkey: &str, value: &str) -> io::Result<()> {
+    let key = Key::open(hive, subkey, KEY_SET_VALUE)?;
+    let w_value = to_wide(value);
+
+    unsafe {
+        let status = RegDeleteValueW(key.0, w_value.as_ptr());
+        if status != 0 {
+            return Err(io::Error::from_raw_os_error(status));
+        }
+    }
+
+    Ok(())
+}
+
+/// Deletes the key `hive\subkey` and its values. It fails if the key has subkeys.
+pub fn delete_key(hive: Hive, subkey: &str) -> io::Result<()> {
+    let w_subkey = to_wide(subkey);
+
+    unsafe {
+        let status = RegDeleteKeyW(hive.hkey(), w_subkey.as_ptr());
+        if status != 0 {
+            return Err(io::Error::from_raw_os_error(status));
+        }
+    }
+
+    Ok(())
+}
+
+impl Hive {
+    /// Returns the handle of the predefined key. They're sign extended on 64-bit.
+    fn hkey(self) -> Hkey {
+        let value = match self {
+            Hive::ClassesRoot => 0x80000000_u32,
+            Hive::CurrentUser => 0x80000001_u32,
+            Hive::LocalMachine => 0x80000002_u32,
+            Hive::Users => 0x80000003_u32,
+            Hive::CurrentConfig => 0x80000005_u32,
+        };
+
+        value as i32 as isize as Hkey
+    }
+}
+
+/// An open registry key, that is closed when dropped.
+struct Key(Hkey);
+
+impl Key {
+    fn open(hive: Hive, subkey: &str, access: u32) -> io::Result<Self> {
+        let w_subkey = to_wide(subkey);
+        let mut key = ptr::null_mut();
+
+        unsafe {
+            let status = RegOpenKeyExW(hive.hkey(), w_subkey.as_ptr(), 0, access, &mut key);
+            if status != 0 {
+                return Err(io::Error::from_raw_os_error(status));
+            }
+        }
+
+        Ok(Self(key))
+    }
+
+    /// Opens the key for writing, and creates it first if it doesn't exist.
+    fn create(hive: Hive, subkey: &str) -> io::Result<Self> {
+        let w_subkey = to_wide(subkey);
+        let mut key = ptr::null_mut();
+
+        unsafe {
+            let status = RegCreateKeyExW(
+                hive.hkey(),
+                w_subkey.as_ptr(),
+                0,
+                ptr::null_mut(),
+                REG_OPTION_NON_VOLATILE,
+                KEY_SET_VALUE | KEY_CREATE_SUB_KEY,
+                ptr::null_mut(),
+                &mut key,
+                ptr::null_mut()
+            );
+
+            if status != 0 {
+                return Err(io::Error::from_raw_os_error(status));
+            }
+        }
+
+        Ok(Self(key))
+    }
+
+    fn set(&self, value: &str, kind: u32, data: &[u8]) -> io::Result<()> {
+        let w_value = to_wide(value);
+
+        unsafe {
+            let status = RegSetValueExW(self.0, w_value.as_ptr(), 0, kind, data.as_ptr(), data.len() as u32);
+            if status != 0 {
+                return Err(io::Error::from_raw_os_error(status));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn query(&self, value: &[u16], kind: &mut u32, data: *mut u8, size: &mut u32) -> io::Result<()> {
+        unsafe {
+            let status = RegQueryValueExW(self.0, value.as_ptr(), ptr::null_mut(), kind, data, size);
+            if status != 0 {
+                return Err(io::Error::from_raw_os_error(status));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        unsafe {
+            RegCloseKey(self.0);
+        }
+    }
+}
+
+#[link(name = "Advapi32")]
+extern "system" {
+    fn RegOpenKeyExW(hKey: Hkey, lpSubKey: *const u16, ulOptions: u32, samDesired: u32, phkResult: *mut Hkey) -> i32;
+    fn RegQueryValueExW(
+        hKey: Hkey,
+        lpValueName: *const u16,
+        lpReserved: *mut u32,
+        lpType: *mut u32,
+        lpData: *mut u8,
+        lpcbData: *mut u32
+    ) -> i32;
+    fn RegCreateKeyExW(
+        hKey: Hkey,
+        lpSubKey: *const u16,
+        Reserved: u32,
+        lpClass: *mut u16,
+        dwOptions: u32,
+        samDesired: u32,
+        lpSecurityAttributes: *mut c_void,
+        phkResult: *mut Hkey,
+        lpdwDisposition: *mut u32
+    ) -> i32;
+    fn RegSetValueExW(hKey: Hkey, lpValueName: *const u16, Reserved: u32, dwType: u32, lpData: *const u8, cbData: u32) -> i32;
+    fn RegDeleteValueW(hKey: Hkey, lpValueName: *const u16) -> i32;
+    fn RegDeleteKeyW(hKey: Hkey, lpSubKey: *const u16) -> i32;
+    fn RegCloseKey(hKey: Hkey) -> i32;
+}
@@ -0,0 +1,169 @@
+use ::std::{collections::HashMap, ffi::c_void, fmt, io, ptr};
+use crate::window::{HBRUSH, HICON};
+use crate::wstring::WideString;
+
+const IMAGE_ICON: u32 = 1;
+const LR_DEFAULTSIZE: u32 = 0x40;
+const LR_LOADFROMFILE: u32 = 0x10;
+
+type Loader = Box<dyn FnMut(&WideString) -> io::Result<GdiObject>>;
+
+/// A GDI handle owned by a [`ResourceCache`]. The cache only lends it out, so it can't be
+/// kept after the cache frees it. Use [`handle`](Self::handle) to pass it to the Windows API.
+#[derive(Debug, PartialEq, Eq)]
+pub enum GdiObject {
+    Icon(HICON),
+    Brush(HBRUSH),
+}
+
+impl GdiObject {
+    /// Loads an icon file (`.ico`) from `path` in the default icon size.
+    pub fn icon_from_file(path: &WideString) -> io::Result<Self> {
+        unsafe {
+            let handle = LoadImageW(
+                ptr::null_mut(),
+                path.ptr(),
+                IMAGE_ICON,
+                0,
+                0,
+                LR_LOADFROMFILE | LR_DEFAULTSIZE
+            );
+
+            if handle.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self::Icon(handle))
+        }
+    }
+
+    /// Creates a solid brush from a `0x00BBGGRR` color.
+    pub fn solid_brush(color: u32) -> io::Result<Self> {
+        unsafe {
+            let handle = CreateSolidBrush(color);
+
+            if handle.is_null() {
+                return Err(io::Error::other("Could not create the brush."));
+            }
+
+            Ok(Self::Brush(handle))
+        }
+    }
+
+    /// Returns the raw handle.
+    pub fn handle(&self) -> *mut c_void {
+        match *self {
+            Self::Icon(handle) => handle,
+            Self::Brush(handle) => handle,
+        }
+    }
+
+    fn free(self) {
+        unsafe {
+            match self {
+                Self::Icon(handle) => DestroyIcon(handle),
+                Self::Brush(handle) => DeleteObject(handle),
+            };
+        }
+    }
+}
+
+/// A cache for icons and brushes, so the same resource is only loaded once.
+/// Everything in the cache is freed, when it's dropped.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::{window::ResourceCache, wstring::WideString};
+///
+/// let mut cache = ResourceCache::new();
+/// let icon = cache.get(&WideString::from("app.ico")).unwrap();
+/// ```
+pub struct ResourceCache {
+    loader: Loader,
+    objects: HashMap<WideString, GdiObject>,
+}
+
+impl ResourceCache {
+    /// Creates an empty cache, that loads icon files on a miss.
+    pub fn new() -> Self {
+        Self::with_loader(GdiObject::icon_from_file)
+    }
+
+    /// Creates an empty cache, that calls `loader` with the name on a miss.
+    /// The cache takes ownership of the handles `loader` returns.
+    pub fn with_loader<L>(loader: L) -> Self
+    where
+        L: FnMut(&WideString) -> io::Result<GdiObject> + 'static,
+    {
+        Self {
+            loader: Box::new(loader),
+            objects: HashMap::new(),
+        }
+    }
+
+    /// Returns the resource called `name`, and loads it first, if it's not in the cache.
+    pub fn get(&mut self, name: &WideString) -> io::Result<&GdiObject> {
+        if !self.objects.contains_key(name) {
+            let object = (self.loader)(name)?;
+            self.objects.insert(name.clone(), object);
+        }
+
+        Ok(&self.objects[name])
+    }
+
+    /// Returns the amount of cached resources.
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    /// Returns `true` if nothing is cached.
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    /// Frees every cached resource.
+    pub fn clear(&mut self) {
+        for (_, object) in self.objects.drain() {
+            object.free();
+        }
+    }
+}
+
+impl Default for ResourceCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ResourceCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResourceCache")
+            .field("objects", &self.objects.len())
+            .finish()
+    }
+}
+
+impl Drop for ResourceCache {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+#[link(name = "User32")]
+extern "system" {
+    fn LoadImageW(
+        hInst: *mut c_void,
+        name: *const u16,
+        type_: u32,
+        cx: i32,
+        cy: i32,
+        fuLoad: u32
+    ) -> *mut c_void;
+    fn DestroyIcon(hIcon: HICON) -> i32;
+}
+
+#[link(name = "Gdi32")]
+extern "system" {
+    fn CreateSolidBrush(color: u32) -> HBRUSH;
+    fn DeleteObject(ho: *mut c_void) -> i32;
+}
@@ -1,22 +1,31 @@
-use std::{ffi::c_void, ptr};
+use std::{ffi::c_void, ptr, sync::{Mutex, MutexGuard}};
 
 use crate::wstring::WideString;
 
 use super::*;
 
+/// Held by the tests, that change the current or DLL directory of the process, since the
+/// tests run in parallel.
+static DIRECTORIES: Mutex<()> = Mutex::new(());
+
+fn lock_directories() -> MutexGuard<'static, ()> {
+    // A failed test poisons it, but its drop guards have restored the directories by then.
+    DIRECTORIES.lock().unwrap_or_else(|error| error.into_inner())
+}
+
 #[test]
 fn wide_str() {
     let wide = wstring::WideString::from("Hello world!");
-    let wstr = wstring::WideStr::from(wide.ptr());
+    let wstr = unsafe { wstring::WideStr::from_raw_ptr(wide.ptr()) };
 
     println!("'{}'", wstr.to_string());
     println!("{:?}", wstr);
 }
 
+type MsgBoxProc = extern "system" fn(*const c_void, *const u16, *const u16, i32);
+
 #[test]
 fn lib() {
-    type MsgBoxProc = extern "system" fn(*const c_void, *const u16, *const u16, i32);
-
     let user32 = utils::Library::load("User32.dll").unwrap();
     let func: utils::FnWrapper<MsgBoxProc> = user32.load_func("MessageBoxW");
 
@@ -29,3 +38,1694 @@ fn lib() {
     msgbox(ptr::null(), msg.ptr(), 0 as _, 0);
 }
 
+
+#[test]
+fn wide_wire() {
+    let texts = ["Hello world!", "", "Æblegrød 🦀"];
+
+    let mut buffer = Vec::new();
+    for text in texts.iter() {
+        buffer.extend(WideString::from(*text).to_wire());
+    }
+
+    let mut offset = 0;
+    for text in texts.iter() {
+        let (wide, used) = WideString::from_wire(&buffer[offset..]).unwrap();
+        assert_eq!(wide.to_string(), *text);
+        offset += used;
+    }
+
+    assert_eq!(offset, buffer.len());
+    assert!(WideString::from_wire(&buffer[..6]).is_err());
+}
+
+#[cfg(feature = "ipc")]
+#[test]
+fn named_pipe() {
+    let mut server = ipc::NamedPipeServer::create("winutils_test_pipe").unwrap();
+
+    let echo = std::thread::spawn(move || {
+        server.wait_connection().unwrap();
+        let msg = server.read_message().unwrap();
+        server.write_message(&msg).unwrap();
+    });
+
+    let client = ipc::NamedPipeClient::connect("winutils_test_pipe").unwrap();
+    client.write_message(&WideString::from("Hello through a pipe!")).unwrap();
+
+    assert_eq!(client.read_message().unwrap().to_string(), "Hello through a pipe!");
+    echo.join().unwrap();
+}
+
+#[cfg(feature = "ipc")]
+#[test]
+fn named_pipe_too_long() {
+    use std::io::Write;
+
+    let mut server = ipc::NamedPipeServer::create("winutils_test_pipe_too_long").unwrap();
+
+    let reader = std::thread::spawn(move || {
+        server.wait_connection().unwrap();
+        server.read_message().unwrap_err().kind()
+    });
+
+    // A frame header that claims ~4 billion code units.
+    let mut client = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(r"\\.\pipe\winutils_test_pipe_too_long")
+        .unwrap();
+    client.write_all(&u32::MAX.to_le_bytes()).unwrap();
+
+    assert_eq!(reader.join().unwrap(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn resource_cache() {
+    use std::{cell::Cell, rc::Rc};
+
+    let loads = Rc::new(Cell::new(0));
+    let counter = loads.clone();
+    let mut cache = window::ResourceCache::with_loader(move |_| {
+        counter.set(counter.get() + 1);
+        window::GdiObject::solid_brush(0x00ff0000)
+    });
+
+    let name = WideString::from("background");
+    let first = cache.get(&name).unwrap().handle();
+    let second = cache.get(&name).unwrap().handle();
+
+    assert!(!first.is_null());
+    assert_eq!(first, second);
+    assert_eq!(loads.get(), 1);
+    assert_eq!(cache.len(), 1);
+}
+
+#[test]
+fn window_handle_display() {
+    struct Static;
+    impl window::Windowing for Static {}
+
+    // The predefined classes don't use the state.
+    let h_wnd = unsafe { window::Windowing::create_window(&mut Static, "STATIC", "Hello title", 200, 100) };
+    let text = h_wnd.to_string();
+
+    assert!(text.contains("\"Hello title\""));
+    assert!(text.to_lowercase().contains("class=static"));
+    assert!(window::WindowHandle::NULL.to_string().contains("invalid"));
+}
+
+#[test]
+fn wide_add_assign() {
+    let mut wide = WideString::from("Hello");
+
+    wide += " ";
+    assert_eq!(wide.bytes.len(), 7);
+
+    wide += &WideString::from("world");
+    assert_eq!(wide.bytes.len(), 12);
+
+    wide += '!';
+    wide += '🦀';
+    assert_eq!(wide.bytes.len(), 15);
+
+    assert_eq!(wide.to_string(), "Hello world!🦀");
+    assert_eq!(wide.bytes.iter().filter(|&&c| c == 0).count(), 1);
+    assert_eq!(wide.bytes.last(), Some(&0));
+}
+
+#[test]
+fn window_message() {
+    let first = window::register_window_message("winutils_test_message");
+    let second = window::register_window_message("winutils_test_message");
+    let other = window::register_window_message("winutils_other_message");
+
+    assert_ne!(first, 0);
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+}
+
+#[test]
+fn window_class_name() {
+    struct Button;
+    impl window::Windowing for Button {}
+
+    // The predefined classes don't use the state.
+    let h_wnd = unsafe { window::Windowing::create_window(&mut Button, "BUTTON", "", 200, 100) };
+
+    assert_eq!(window::class_name(h_wnd).unwrap().to_string().to_lowercase(), "button");
+    assert!(window::class_name(window::WindowHandle::NULL).is_err());
+}
+
+#[test]
+fn working_set() {
+    // There's no guarantee the memory usage visibly drops, only that the call works.
+    assert!(utils::trim_working_set().is_ok());
+}
+
+#[test]
+fn environment_block() {
+    let vars = utils::environment_block().unwrap();
+
+    assert!(vars.iter().any(|(name, _)| name.to_string().eq_ignore_ascii_case("PATH")));
+    assert!(vars.iter().all(|(name, _)| !name.to_string().starts_with('=')));
+}
+
+#[test]
+fn env_block() {
+    let block = utils::build_env_block(&[("b", "2"), ("A", "1")]);
+    let expected: Vec<u16> = "A=1\0b=2\0\0".encode_utf16().collect();
+
+    assert_eq!(block, expected);
+    assert_eq!(utils::build_env_block(&[]), vec![0, 0]);
+}
+
+#[test]
+fn copy_data() {
+    use window::Windowing;
+
+    #[derive(Default)]
+    struct Receiver {
+        id: usize,
+        data: Vec<u8>,
+    }
+
+    impl Windowing for Receiver {
+        fn on_copy_data(&mut self, id: usize, data: &[u8]) {
+            self.id = id;
+            self.data = data.to_vec();
+        }
+    }
+
+    struct Sender;
+    impl Windowing for Sender {}
+
+    let mut receiver = window::Window::new(Receiver::default());
+    receiver.register("winutils_copy_data_window").unwrap();
+    let target = receiver.create_window("winutils_copy_data_window", "Receiver", 200, 100);
+
+    // The predefined classes don't use the state.
+    let from = unsafe { Windowing::create_window(&mut Sender, "STATIC", "Sender", 200, 100) };
+    assert!(!target.is_null() && !from.is_null());
+
+    let payload = [0_u8, 1, 2, 127, 255];
+    assert_eq!(window::send_copy_data(target, from, 7, &payload), 1);
+    assert_eq!(receiver.data().id, 7);
+    assert_eq!(receiver.data().data, payload);
+
+    // Windows that don't handle `WM_COPYDATA` return 0.
+    assert_eq!(window::send_copy_data(from, target, 7, &payload), 0);
+}
+
+#[test]
+fn wide_eq() {
+    let wide = WideString::from("Hello");
+
+    assert_eq!(wide, wide.clone());
+    assert_eq!(wide, WideString::from_str_with_size("Hello", 32));
+    assert_ne!(wide, WideString::from("Hello!"));
+    assert_eq!(wide, "Hello");
+    assert_ne!(wide, "Hell");
+
+    assert_eq!(WideString::default(), WideString::with_size(8));
+    assert_ne!(WideString::empty(), WideString::default());
+}
+
+#[test]
+fn wide_len() {
+    let wide = WideString::from("Hello");
+    assert_eq!(wide.len(), 5);
+    assert!(!wide.is_empty());
+
+    let padded = WideString::from_str_with_size("Hello", 32);
+    assert_eq!(padded.len(), 5);
+
+    assert_eq!(WideString::empty().len(), 0);
+    assert!(WideString::empty().is_empty());
+    assert!(WideString::with_size(8).is_empty());
+}
+
+#[test]
+fn wide_decode_into() {
+    let mut out = String::new();
+
+    WideString::from("Hello world!").decode_into(&mut out);
+    assert_eq!(out, "Hello world!");
+
+    WideString::from("Bye").decode_into(&mut out);
+    assert_eq!(out, "Bye");
+
+    let invalid = WideString { bytes: vec![0x61, 0xD800, 0x62, 0] };
+    invalid.decode_into(&mut out);
+    assert_eq!(out, String::from_utf16_lossy(&[0x61, 0xD800, 0x62]));
+}
+
+#[test]
+fn wstr_escapes() {
+    let tab: [u16; 4] = wstring::wstr!("a\tb");
+    assert_eq!(tab, [0x61, 0x09, 0x62, 0x00]);
+
+    let escaped: [u16; 8] = wstring::wstr!("\n\\\"\u{e6}", 8);
+    assert_eq!(escaped, [0x0A, 0x5C, 0x22, 0xE6, 0, 0, 0, 0]);
+}
+
+#[test]
+fn wstr_exact_size() {
+    let font_name: [u16; 9] = wstring::wstr!("Segoe UI", 9);
+    assert_eq!(font_name[8], 0);
+}
+
+#[test]
+fn wstr_inputs() {
+    let comma: [u16; 4] = wstring::wstr!("a,b");
+    assert_eq!(comma, [0x61, 0x2C, 0x62, 0x00]);
+
+    let joined: [u16; 8] = wstring::wstr!(concat!("a", 'b', 1, concat!("c")), 8);
+    assert_eq!(joined, [0x61, 0x62, 0x31, 0x63, 0, 0, 0, 0]);
+}
+
+#[test]
+fn wstr_len() {
+    assert_eq!(wstring::wstrlen!("Segoe UI"), 8);
+    assert_eq!(wstring::wstrlen!("🦀"), 2);
+    assert_eq!(wstring::wstrlen!("Segoe UI", nul), 9);
+
+    let font_name: [u16; wstring::wstrlen!("Segoe UI", nul)] = wstring::wstr!("Segoe UI");
+    assert_eq!(font_name.last(), Some(&0));
+}
+
+#[test]
+fn lib_error() {
+    let error = utils::Library::load("winutils_missing.dll").unwrap_err();
+
+    // ERROR_MOD_NOT_FOUND, with the system message in front of the code.
+    assert_eq!(error.raw_os_error(), Some(126));
+    assert!(error.to_string().len() > "(os error 126)".len());
+
+    let kernel32 = utils::Library::get_static_lib("Kernel32.dll").unwrap();
+    assert!(kernel32.try_load_func::<MsgBoxProc>("NotAFunction").is_err());
+}
+
+#[test]
+fn lib_flags() {
+    let flags = utils::LOAD_LIBRARY_AS_DATAFILE | utils::LOAD_LIBRARY_SEARCH_SYSTEM32;
+    let shell32 = utils::Library::load_with_flags("Shell32.dll", flags).unwrap();
+
+    assert!(!shell32.handle().is_null());
+    assert!(matches!(shell32.lib_type(), utils::LibType::Dynamic));
+}
+
+#[test]
+fn lib_ordinal() {
+    type WsaStartupProc = extern "system" fn(u16, *mut c_void) -> i32;
+
+    // Ordinal 115 of Ws2_32.dll is `WSAStartup`.
+    let ws2_32 = utils::Library::load("Ws2_32.dll").unwrap();
+    let func: utils::FnWrapper<WsaStartupProc> = ws2_32.load_func_ordinal(115);
+
+    assert!(func.is_valid());
+    assert!(!ws2_32.load_func_ordinal::<WsaStartupProc>(u16::MAX).is_valid());
+}
+
+#[test]
+fn lib_path() {
+    let kernel32 = utils::Library::load("Kernel32.dll").unwrap();
+    let path = kernel32.path().unwrap().to_string().to_lowercase();
+
+    assert!(path.ends_with("kernel32.dll"));
+    assert!(utils::Library::empty().path().is_err());
+}
+
+#[test]
+fn lib_send() {
+    type GetTickCountProc = extern "system" fn() -> u32;
+
+    let kernel32 = utils::Library::load("Kernel32.dll").unwrap();
+
+    let ticks = std::thread::spawn(move || {
+        let func: utils::FnWrapper<GetTickCountProc> = kernel32.load_func("GetTickCount");
+        func.unwrap()()
+    })
+    .join()
+    .unwrap();
+
+    assert!(ticks > 0);
+}
+
+#[test]
+fn lib_fn_get() {
+    type GetTickCountProc = extern "system" fn() -> u32;
+
+    let user32 = utils::Library::load("User32.dll").unwrap();
+    let msgbox: utils::FnWrapper<MsgBoxProc> = user32.load_func("MessageBoxW");
+    assert!(msgbox.get().is_some());
+    assert!(msgbox.as_ref().is_some());
+
+    let kernel32 = utils::Library::load("Kernel32.dll").unwrap();
+    let func: utils::FnWrapper<GetTickCountProc> = kernel32.load_func("GetTickCount");
+
+    let first = func.get().unwrap()();
+    let second = func.get().unwrap()();
+    assert!(second >= first);
+
+    func.expect("GetTickCount is missing");
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SizedStruct {
+    size: u32,
+    value: u16,
+    _pad: u16,
+}
+
+unsafe impl utils::Pod for SizedStruct {}
+
+#[test]
+fn buffer_as_ref() {
+    let mut buffer = utils::DynBuffer::new();
+    assert!(buffer.as_ref::<SizedStruct>().is_none());
+
+    unsafe {
+        let ptr = buffer.as_dyn_ptr::<SizedStruct>(2);
+        ptr.write(SizedStruct { size: 8, value: 1, _pad: 0 });
+        ptr.add(1).write(SizedStruct { size: 8, value: 2, _pad: 0 });
+    }
+
+    assert_eq!(buffer.as_ref::<SizedStruct>(), Some(&SizedStruct { size: 8, value: 1, _pad: 0 }));
+    assert_eq!(buffer.as_slice::<SizedStruct>().len(), 2);
+    assert_eq!(buffer.as_slice::<SizedStruct>()[1].value, 2);
+    assert_eq!(buffer.as_slice::<u32>().len(), 4);
+}
+
+#[test]
+fn buffer_clear() {
+    let mut buffer = utils::DynBuffer::with_capacity(64);
+
+    buffer.as_dyn_ptr::<u32>(4);
+    assert_eq!(buffer.as_bytes().len(), 16);
+
+    let capacity = buffer.capacity();
+    buffer.clear();
+    assert!(buffer.is_empty());
+    assert!(buffer.as_bytes().is_empty());
+    assert_eq!(buffer.capacity(), capacity);
+
+    buffer.as_dyn_ptr::<u16>(3);
+    assert_eq!(buffer.as_bytes(), &[0; 6]);
+}
+
+#[test]
+fn buffer_write_read() {
+    let mut buffer = utils::DynBuffer::new();
+    buffer.write(0xDEADBEEF_u32);
+    buffer.write(42_u32);
+    buffer.write(7_u16);
+    assert_eq!(buffer.as_bytes().len(), 10);
+
+    assert_eq!(buffer.read::<u32>(0), Some(0xDEADBEEF));
+    assert_eq!(buffer.read::<u32>(4), Some(42));
+    assert_eq!(buffer.read::<u16>(8), Some(7));
+    assert_eq!(buffer.read::<u32>(8), None);
+    assert_eq!(buffer.read::<u8>(usize::MAX), None);
+}
+
+#[test]
+fn buffer_struct() {
+    let value = SizedStruct { size: std::mem::size_of::<SizedStruct>() as u32, value: 3, _pad: 0 };
+    let mut buffer = utils::DynBuffer::from_struct(value);
+    assert_eq!(buffer.as_bytes().len(), std::mem::size_of::<SizedStruct>());
+    assert_eq!(buffer.into_struct::<SizedStruct>(), Some(value));
+    assert_eq!(buffer.as_bytes(), &[8, 0, 0, 0, 3, 0, 0, 0]);
+
+    // Like an API changing the struct through the pointer.
+    unsafe { (*buffer.as_dyn_ptr::<SizedStruct>(1)).value = 4 };
+    assert_eq!(buffer.into_struct::<SizedStruct>().unwrap().value, 4);
+    assert_eq!(buffer.into_struct::<[SizedStruct; 2]>(), None);
+}
+
+thread_local! {
+    static DROPS: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+// Zero-sized, so `Box::into_raw` doesn't allocate anything that would leak.
+struct DropCounter;
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        DROPS.with(|drops| drops.set(drops.get() + 1));
+    }
+}
+
+#[test]
+fn unique_ptr() {
+    DROPS.with(|drops| drops.set(0));
+
+    let unique = unsafe { utils::UniquePtr::from_raw(Box::into_raw(Box::new(DropCounter))) };
+    assert!(!unique.is_null());
+    let _: &DropCounter = &unique;
+    drop(unique);
+    assert_eq!(DROPS.with(|drops| drops.get()), 1);
+
+    let unique = unsafe { utils::UniquePtr::from_raw(Box::into_raw(Box::new(DropCounter))) };
+    let raw = unique.into_raw();
+    assert_eq!(DROPS.with(|drops| drops.get()), 1);
+    drop(unsafe { Box::from_raw(raw) });
+    assert_eq!(DROPS.with(|drops| drops.get()), 2);
+
+    drop(utils::UniquePtr::<DropCounter>::null());
+    assert_eq!(DROPS.with(|drops| drops.get()), 2);
+}
+
+#[test]
+fn owned_ptr() {
+    thread_local! {
+        static DELETES: std::cell::Cell<usize> = std::cell::Cell::new(0);
+    }
+
+    fn deleter(ptr: *mut u32) {
+        assert!(!ptr.is_null());
+        DELETES.with(|deletes| deletes.set(deletes.get() + 1));
+    }
+
+    let mut value = 5_u32;
+    let owned = unsafe { utils::OwnedPtr::new(&mut value as *mut u32, deleter) };
+    assert_eq!(*owned, 5);
+
+    drop(owned);
+    assert_eq!(DELETES.with(|deletes| deletes.get()), 1);
+
+    drop(unsafe { utils::OwnedPtr::new(ptr::null_mut(), deleter) });
+    assert_eq!(DELETES.with(|deletes| deletes.get()), 1);
+}
+
+#[test]
+fn wide_push_char() {
+    let mut wide = WideString::from("Caf");
+    wide.push_char('é');
+    assert_eq!(wide.bytes, [0x43, 0x61, 0x66, 0xE9, 0]);
+
+    wide.push_char('🦀');
+    assert_eq!(&wide.bytes[4..], &[0xD83E, 0xDD80, 0]);
+
+    wide.extend("!?".chars());
+    wide.extend(vec![" ok", ""]);
+    assert_eq!(wide.to_string(), "Café🦀!? ok");
+    assert_eq!(wide.bytes.iter().filter(|&&c| c == 0).count(), 1);
+
+    let mut padded = WideString::from_str_with_size("Hi", 8);
+    padded += '!';
+    assert_eq!(padded.bytes, [0x48, 0x69, 0x21, 0]);
+
+    let mut buffer = WideString::with_size(4);
+    buffer.extend("ok".chars());
+    assert_eq!(buffer.len(), 2);
+    assert_eq!(buffer.to_string(), "ok");
+}
+
+#[test]
+fn wide_from_units() {
+    let terminated: &[u16] = &[0x48, 0x69, 0];
+    let unterminated: &[u16] = &[0x48, 0x69];
+
+    assert_eq!(WideString::from(terminated).bytes, [0x48, 0x69, 0]);
+    assert_eq!(WideString::from(unterminated).bytes, [0x48, 0x69, 0]);
+    assert_eq!(WideString::from(&[0x48_u16, 0, 0, 0][..]).bytes, [0x48, 0]);
+
+    let collected: WideString = "Hi".encode_utf16().collect();
+    assert_eq!(collected, "Hi");
+    assert_eq!(collected.bytes.len(), 3);
+}
+
+#[test]
+fn wide_slices() {
+    let wide = WideString::from("Hi");
+    let padded = WideString::from_str_with_size("Hi", 8);
+
+    for wide in [&wide, &padded].iter() {
+        assert_eq!(wide.as_slice(), &[0x48, 0x69]);
+        assert_eq!(wide.as_slice_with_nul(), &[0x48, 0x69, 0]);
+        assert_eq!(wide.iter().collect::<Vec<u16>>(), [0x48, 0x69]);
+        assert_eq!(AsRef::<[u16]>::as_ref(*wide), &[0x48, 0x69]);
+    }
+
+    assert!(WideString::empty().as_slice_with_nul().is_empty());
+}
+
+#[test]
+fn wide_case() {
+    let upper = WideString::from("hello").to_upper();
+    assert_eq!(upper, "HELLO");
+    assert_eq!(upper.bytes.last(), Some(&0));
+
+    let lower = WideString::from_str_with_size("HeLLo", 8).to_lower();
+    assert_eq!(lower, "hello");
+    assert_eq!(lower.bytes.len(), 8);
+}
+
+#[test]
+fn wide_add() {
+    let a = WideString::from("Hello");
+    let b = WideString::from(" world");
+
+    let joined = a + &b + "!";
+    assert_eq!(joined, "Hello world!");
+    assert_eq!(joined.bytes.len(), 13);
+    assert!(!joined.bytes[..12].contains(&0));
+
+    let mut ws = WideString::from("Hello ");
+    ws += "world";
+    assert_eq!(ws.bytes.len(), 12);
+    assert!(!ws.bytes[..11].contains(&0));
+
+    let padded = WideString::from_str_with_size("Hello", 16) + &WideString::from_str_with_size(" world", 16);
+    assert_eq!(padded, "Hello world");
+    assert_eq!(padded.bytes.len(), 12);
+    assert_eq!(padded.bytes.last(), Some(&0));
+    assert!(!padded.bytes[..11].contains(&0));
+}
+
+#[test]
+fn wide_find() {
+    let wide = WideString::from_str_with_size("C:\\🦀\\crab.rs", 32);
+
+    assert_eq!(wide.find("C:"), Some(0));
+    assert_eq!(wide.find("🦀"), Some(3));
+    assert_eq!(wide.find(".rs"), Some(10));
+    assert_eq!(wide.find("\0"), None);
+    assert!(wide.contains("crab"));
+    assert!(!wide.contains("crabs"));
+
+    assert!(wide.starts_with("C:\\🦀"));
+    assert!(wide.starts_with(""));
+    assert!(!wide.starts_with("D:"));
+}
+
+#[test]
+fn wide_str_len() {
+    let wide = WideString::from_str_with_size("Hello", 16);
+
+    let scanned = unsafe { wstring::WideStr::from_raw_ptr(wide.ptr()) };
+    let sliced = wstring::WideStr::from_slice(&wide.bytes);
+    let provided = unsafe { wstring::WideStr::from_ptr_len(wide.ptr(), 5) };
+
+    assert_eq!(scanned.len(), 5);
+    assert_eq!(sliced.len(), scanned.len());
+    assert_eq!(provided.len(), scanned.len());
+    assert_eq!(provided.to_string(), "Hello");
+    assert_eq!(sliced.as_bytes().len(), 6);
+
+    let unterminated = [0x48_u16, 0x69];
+    assert_eq!(wstring::WideStr::from_slice(&unterminated).len(), 2);
+    assert_eq!(wstring::WideStr::from_slice(&unterminated).to_string(), "Hi");
+    assert_eq!(wstring::WideStr::from_slice(&unterminated).read_part(1), "H");
+    assert_eq!(wstring::WideStr::from_slice(&unterminated).read_part(100), "Hi");
+}
+
+#[test]
+fn known_folder() {
+    let path = utils::get_known_folder(&utils::FOLDERID_LOCAL_APP_DATA).unwrap();
+
+    assert!(!path.is_empty());
+    assert!(std::path::Path::new(&path.to_string()).is_absolute());
+}
+
+#[test]
+#[ignore = "changes the clipboard of the user"]
+fn clipboard() {
+    utils::set_clipboard_text("Hello clipboard! 🦀").unwrap();
+    assert_eq!(utils::get_clipboard_text().unwrap(), "Hello clipboard! 🦀");
+}
+
+#[test]
+fn expand_env_strings() {
+    let root = std::env::var("SystemRoot").unwrap();
+    assert_eq!(utils::expand_env_strings(r"%SystemRoot%\System32").unwrap(), format!(r"{}\System32", root).as_str());
+    assert_eq!(utils::expand_env_strings("No variables here").unwrap(), "No variables here");
+    assert_eq!(utils::expand_env_strings("").unwrap(), "");
+}
+
+#[test]
+fn error_message() {
+    let message = utils::error_message(2);
+    assert!(!message.is_empty());
+    assert!(!message.ends_with(['\r', '\n']));
+
+    let library = utils::Library::load("surely_not_a_real_library.dll");
+    assert!(library.is_err());
+    assert!(!utils::last_error_message().is_empty());
+}
+
+#[test]
+fn message_box_flags() {
+    use utils::{MessageBox, MessageBoxButtons, MessageBoxDefault, MessageBoxIcon};
+
+    assert_eq!(MessageBox::new("Text").flags(), 0);
+
+    let mb = MessageBox::new("Save changes?")
+        .title("Title")
+        .buttons(MessageBoxButtons::YesNoCancel)
+        .icon(MessageBoxIcon::Warning)
+        .default_button(MessageBoxDefault::Second);
+
+    // MB_YESNOCANCEL | MB_ICONWARNING | MB_DEFBUTTON2
+    assert_eq!(mb.flags(), 0x3 | 0x30 | 0x100);
+}
+
+#[test]
+fn msgbox_owned() {
+    // MessageBoxW fails straight away, when the owner isn't a window.
+    let dummy = window::WindowHandle(0x1234 as window::HWND);
+    assert_eq!(utils::msgbox_owned(dummy, "Not shown", None, 0), utils::MBResult::Error);
+    assert_eq!(utils::MessageBox::new("Not shown").owner(dummy).show(), utils::MBResult::Error);
+}
+
+#[test]
+#[ignore = "shows a message box"]
+fn msgbox_owned_null() {
+    assert_eq!(utils::msgbox_owned(window::WindowHandle::NULL, "Click OK", Some("Test"), 0), utils::MBResult::Ok);
+}
+
+#[test]
+fn com_init_ex() {
+    use init::{ComInit, COINIT_APARTMENT_THREADED, COINIT_DISABLE_OLE1DDE, COINIT_SPEED_OVER_MEMORY};
+
+    assert_eq!(COINIT_APARTMENT_THREADED | COINIT_DISABLE_OLE1DDE | COINIT_SPEED_OVER_MEMORY, 0xE);
+
+    // Every test runs on its own thread, so COM isn't initialized yet.
+    let _com = ComInit::init_ex(COINIT_APARTMENT_THREADED | COINIT_DISABLE_OLE1DDE).unwrap();
+}
+
+#[test]
+fn com_init_twice() {
+    let first = init::ComInit::init_sta().unwrap();
+    // S_FALSE, since COM is already initialized on this thread.
+    let second = init::ComInit::init_sta().unwrap();
+    assert!(init::ComInit::init_mta().is_err());
+
+    drop(second);
+    drop(first);
+}
+
+#[test]
+fn guid_parse() {
+    use guid::GUID;
+
+    let expected = GUID::from_u128(0x00021401_0000_0000_C000_000000000046);
+    assert_eq!(GUID::parse("{00021401-0000-0000-C000-000000000046}"), Some(expected));
+    assert_eq!(GUID::parse("00021401-0000-0000-c000-000000000046"), Some(expected));
+    assert_eq!(crate::guid!("00021401-0000-0000-C000-000000000046"), expected);
+
+    assert_eq!(GUID::parse("{00021401-0000-0000-C000-000000000046"), None);
+    assert_eq!(GUID::parse("00021401_0000_0000_C000_000000000046"), None);
+    assert_eq!(GUID::parse("0002140G-0000-0000-C000-000000000046"), None);
+}
+
+#[test]
+fn co_create_instance() {
+    #[repr(C)]
+    struct IUnknownVtbl {
+        query_interface: *const c_void,
+        add_ref: *const c_void,
+        release: unsafe extern "system" fn(*mut IUnknown) -> u32,
+    }
+
+    #[repr(C)]
+    struct IUnknown {
+        vtbl: *const IUnknownVtbl,
+    }
+
+    let _com = init::ComInit::init_sta().unwrap();
+
+    let clsid_shell_link = crate::guid!("{00021401-0000-0000-C000-000000000046}");
+    let iid_shell_link = crate::guid!("{000214F9-0000-0000-C000-000000000046}");
+
+    let link: *mut IUnknown = init::co_create_instance(&clsid_shell_link, &iid_shell_link).unwrap();
+    assert!(!link.is_null());
+
+    unsafe {
+        ((*(*link).vtbl).release)(link);
+    }
+}
+
+#[test]
+fn guid_display() {
+    use guid::GUID;
+
+    let text = "{3EB685DB-65F9-4CF6-A03A-E3EF65729F3D}";
+    let guid: GUID = text.parse().unwrap();
+    assert_eq!(guid, utils::FOLDERID_ROAMING_APP_DATA);
+    assert_eq!(guid.to_string(), text);
+    assert!("not a guid".parse::<GUID>().is_err());
+
+    let first = GUID::new_random().unwrap();
+    let second = GUID::new_random().unwrap();
+    assert_ne!(first, second);
+    assert_eq!(first.to_string().parse::<GUID>().unwrap(), first);
+}
+
+#[test]
+fn window_create() {
+    use window::Windowing;
+
+    struct Blank;
+    impl Windowing for Blank {}
+
+    let mut window = window::Window::new(Blank);
+    assert!(window.hwnd().is_null());
+
+    window.register("winutils_test_window").unwrap();
+    let h_wnd = window.create_window("winutils_test_window", "Test window", 400, 300);
+
+    assert!(!h_wnd.is_null());
+    assert_eq!(window.hwnd(), h_wnd);
+    assert_eq!(window.class(), "winutils_test_window");
+    assert_eq!(window.title(), "Test window");
+    assert_eq!(window::class_name(h_wnd).unwrap(), "winutils_test_window");
+}
+
+#[test]
+fn window_routing() {
+    use window::Windowing;
+
+    #[derive(Default)]
+    struct Receiver {
+        created: bool,
+        data: Vec<u8>,
+    }
+
+    impl Windowing for Receiver {
+        fn on_create(&mut self, _w_param: window::WPARAM, _l_param: window::LPARAM) -> Option<window::LRESULT> {
+            self.created = true;
+            Some(0)
+        }
+
+        fn on_copy_data(&mut self, _id: usize, data: &[u8]) {
+            self.data = data.to_vec();
+        }
+    }
+
+    let mut window = window::Window::new(Receiver::default());
+    window.register("winutils_routing_window").unwrap();
+    let h_wnd = window.create_window("winutils_routing_window", "Routing", 200, 100);
+
+    assert!(!h_wnd.is_null());
+    assert!(window.data().created);
+
+    assert_eq!(window::send_copy_data(h_wnd, window::WindowHandle::NULL, 1, b"routed"), 1);
+    assert_eq!(window.data().data, b"routed");
+
+    // Messages sent while the state is borrowed go to DefWindowProcW.
+    let data = window.data();
+    assert_eq!(window::send_copy_data(h_wnd, window::WindowHandle::NULL, 1, b"ignored"), 0);
+    assert_eq!(data.data, b"routed");
+    drop(data);
+
+    // The state stays in place when the window is moved.
+    let windows = vec![window];
+    assert_eq!(window::send_copy_data(h_wnd, window::WindowHandle::NULL, 1, b"moved"), 1);
+    assert_eq!(windows[0].data().data, b"moved");
+}
+
+#[test]
+fn window_close() {
+    use window::Windowing;
+
+    #[derive(Default)]
+    struct Closing {
+        closed: bool,
+    }
+
+    impl Windowing for Closing {
+        fn on_close(&mut self, _w_param: window::WPARAM, _l_param: window::LPARAM) -> Option<window::LRESULT> {
+            self.closed = true;
+            // Lets DefWindowProcW destroy the window.
+            None
+        }
+    }
+
+    struct Aborting;
+
+    impl Windowing for Aborting {
+        fn on_create(&mut self, _w_param: window::WPARAM, _l_param: window::LPARAM) -> Option<window::LRESULT> {
+            Some(-1)
+        }
+    }
+
+    let mut aborting = window::Window::new(Aborting);
+    aborting.register("winutils_aborting_window").unwrap();
+    assert!(aborting.create_window("winutils_aborting_window", "Aborted", 200, 100).is_null());
+
+    let mut window = window::Window::new(Closing::default());
+    window.register("winutils_closing_window").unwrap();
+    let h_wnd = window.create_window("winutils_closing_window", "Closing", 200, 100);
+
+    window.wnd_proc(h_wnd, window::WM_CLOSE, 0, 0);
+    assert!(window.data().closed);
+    assert!(h_wnd.to_string().contains("invalid"));
+
+    // The default `on_destroy` posted WM_QUIT, so the loop ends straight away.
+    assert_eq!(window.run(), 0);
+}
+
+#[test]
+fn window_input_hooks() {
+    use window::{Windowing, LRESULT};
+
+    #[derive(Default)]
+    struct Input {
+        size: (i32, i32),
+        timer: usize,
+        mouse: (i32, i32, usize),
+        click: (i32, i32),
+        key: u32,
+    }
+
+    impl Windowing for Input {
+        fn on_size(&mut self, width: i32, height: i32) -> Option<LRESULT> {
+            self.size = (width, height);
+            Some(0)
+        }
+
+        fn on_timer(&mut self, id: usize) -> Option<LRESULT> {
+            self.timer = id;
+            Some(0)
+        }
+
+        fn on_mouse_move(&mut self, x: i32, y: i32, flags: usize) -> Option<LRESULT> {
+            self.mouse = (x, y, flags);
+            Some(0)
+        }
+
+        fn on_left_click(&mut self, x: i32, y: i32) -> Option<LRESULT> {
+            self.click = (x, y);
+            Some(0)
+        }
+
+        fn on_key_down(&mut self, vk: u32) -> Option<LRESULT> {
+            self.key = vk;
+            Some(0)
+        }
+    }
+
+    let mut input = Input::default();
+    let h_wnd = window::WindowHandle::NULL;
+
+    input.wnd_proc(h_wnd, window::WM_SIZE, 0, (300 << 16) | 400);
+    input.wnd_proc(h_wnd, window::WM_TIMER, 42, 0);
+    // x = -2 and y = 5, packed as signed words.
+    input.wnd_proc(h_wnd, window::WM_MOUSEMOVE, 0x1, (5 << 16) | 0xfffe);
+    input.wnd_proc(h_wnd, window::WM_LBUTTONDOWN, 0x1, (0xfff6 << 16) | 20);
+    input.wnd_proc(h_wnd, window::WM_KEYDOWN, 0x41, 0);
+
+    assert_eq!(input.size, (400, 300));
+    assert_eq!(input.timer, 42);
+    assert_eq!(input.mouse, (-2, 5, 0x1));
+    assert_eq!(input.click, (20, -10));
+    assert_eq!(input.key, 0x41);
+}
+
+#[test]
+fn window_builder_flags() {
+    use window::*;
+
+    let builder = WindowBuilder::new("Class");
+    assert_eq!(builder.flags(), (WS_OVERLAPPEDWINDOW, 0));
+
+    let fixed = builder.clone().resizable(false);
+    assert_eq!(fixed.flags().0, WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU | WS_MINIMIZEBOX);
+    assert_eq!(fixed.resizable(true).flags().0, WS_OVERLAPPEDWINDOW);
+
+    let tool = WindowBuilder::new("Class")
+        .style(WS_POPUP | WS_BORDER)
+        .ex_style(WS_EX_TOPMOST | WS_EX_TOOLWINDOW);
+    assert_eq!(tool.flags(), (0x80800000, 0x88));
+}
+
+#[test]
+fn window_builder_create() {
+    use window::{Window, WindowBuilder, Windowing};
+
+    struct Blank;
+    impl Windowing for Blank {}
+
+    let mut window = Window::new(Blank);
+    window.register("winutils_builder_window").unwrap();
+
+    let h_wnd = window.create(
+        &WindowBuilder::new("winutils_builder_window")
+            .title("Builder")
+            .position(10, 20)
+            .size(300, 200)
+    ).unwrap();
+
+    assert_eq!(window.hwnd(), h_wnd);
+    assert_eq!(window.title(), "Builder");
+
+    let builder = WindowBuilder::new("winutils_builder_window");
+    assert_eq!(window.create(&builder).unwrap_err().kind(), std::io::ErrorKind::AlreadyExists);
+    assert_eq!(window.create(&WindowBuilder::new("STATIC")).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+
+    // The class doesn't exist, so no window keeps a pointer to `blank`.
+    let mut blank = Blank;
+    assert!(unsafe { WindowBuilder::new("winutils_not_registered").build(&mut blank) }.is_err());
+}
+
+#[test]
+fn window_icon() {
+    use window::{Window, WindowBuilder, Windowing};
+
+    struct Blank;
+    impl Windowing for Blank {}
+
+    let icon = unsafe { window::load_icon(ptr::null_mut(), window::IDI_APPLICATION) }.unwrap();
+    assert!(unsafe { window::load_icon(Blank.hinstance(), 1) }.is_err());
+    assert!(window::load_icon_file("surely_not_a_real_icon.ico").is_err());
+
+    let mut window = Window::new(Blank);
+    window.register_with_icon("winutils_icon_window", icon).unwrap();
+    assert!(window.create(&WindowBuilder::new("winutils_icon_window").icon(1)).is_err());
+    assert!(window.create(&WindowBuilder::new("winutils_icon_window").icon_handle(icon)).is_ok());
+}
+
+#[test]
+fn standard_resource_ids() {
+    use window::*;
+
+    // The values from WinUser.h.
+    assert_eq!([IDI_APPLICATION, IDI_ERROR, IDI_QUESTION, IDI_WARNING, IDI_INFORMATION], [32512, 32513, 32514, 32515, 32516]);
+    assert_eq!([IDC_ARROW, IDC_IBEAM, IDC_WAIT, IDC_CROSS, IDC_HAND], [32512, 32513, 32514, 32515, 32649]);
+
+    for id in [IDI_APPLICATION, IDI_ERROR, IDI_QUESTION, IDI_WARNING, IDI_INFORMATION, IDI_WINLOGO, IDI_SHIELD] {
+        assert!(unsafe { load_icon(ptr::null_mut(), id) }.is_ok());
+    }
+
+    for id in [IDC_ARROW, IDC_IBEAM, IDC_WAIT, IDC_CROSS, IDC_HAND, IDC_SIZEALL, IDC_NO, IDC_HELP] {
+        assert!(unsafe { load_cursor(ptr::null_mut(), id) }.is_ok());
+    }
+}
+
+#[test]
+fn accelerator_table() {
+    use window::{AcceleratorTable, ACCEL, FCONTROL, FVIRTKEY};
+
+    let table = AcceleratorTable::create(&[
+        ACCEL { fVirt: FVIRTKEY | FCONTROL, key: 0x51, cmd: 100 },
+        ACCEL { fVirt: FVIRTKEY, key: 0x70, cmd: 101 },
+    ]).unwrap();
+
+    assert!(!table.handle().is_null());
+    assert!(unsafe { AcceleratorTable::load(ptr::null_mut(), 1) }.is_err());
+}
+
+#[test]
+fn dialog_proc() {
+    use window::dialog::{self, DialogProc};
+
+    #[derive(Default)]
+    struct Counter {
+        inits: u32,
+        commands: Vec<usize>,
+    }
+
+    impl DialogProc for Counter {
+        fn on_init(&mut self, _h_dlg: window::WindowHandle) -> bool {
+            self.inits += 1;
+            false
+        }
+
+        fn on_command(&mut self, _h_dlg: window::WindowHandle, w_param: window::WPARAM, _l_param: window::LPARAM) -> bool {
+            self.commands.push(w_param & 0xffff);
+            true
+        }
+    }
+
+    let mut counter = Counter::default();
+    assert!(!counter.dlg_proc(window::WindowHandle::NULL, dialog::WM_INITDIALOG, 0, 0));
+    assert!(counter.dlg_proc(window::WindowHandle::NULL, window::WM_COMMAND, 42, 0));
+    assert!(!counter.dlg_proc(window::WindowHandle::NULL, window::WM_PAINT, 0, 0));
+
+    assert_eq!(counter.inits, 1);
+    assert_eq!(counter.commands, [42]);
+
+    // The test executable has no dialog templates.
+    assert!(unsafe { dialog::show_dialog(ptr::null_mut(), 101, window::WindowHandle::NULL, Counter::default()) }.is_err());
+
+    let parent = window::WindowHandle(0x1234 as window::HWND);
+    let error = unsafe { dialog::show_dialog(ptr::null_mut(), 101, parent, Counter::default()) }.unwrap_err();
+    assert_eq!(error.raw_os_error(), Some(1400));
+}
+
+#[test]
+fn dpi_aware() {
+    use init::{set_dpi_aware, DpiAwareness};
+
+    assert!(set_dpi_aware(DpiAwareness::PerMonitorAwareV2).is_ok());
+    // The awareness is already set, which isn't an error.
+    assert!(set_dpi_aware(DpiAwareness::SystemAware).is_ok());
+}
+
+#[test]
+fn registry_read() {
+    use registry::HKEY_CURRENT_USER;
+
+    let temp = registry::read_string(HKEY_CURRENT_USER, "Environment", "TEMP").unwrap();
+    assert!(!temp.is_empty());
+    assert_eq!(temp.bytes.last(), Some(&0));
+
+    let wrong_type = registry::read_u32(HKEY_CURRENT_USER, "Environment", "TEMP").unwrap_err();
+    assert_eq!(wrong_type.kind(), std::io::ErrorKind::InvalidData);
+
+    assert!(registry::read_string(HKEY_CURRENT_USER, "Environment", "winutils_not_a_value").is_err());
+    assert!(registry::read_string(HKEY_CURRENT_USER, r"Software\winutils_not_a_key", "TEMP").is_err());
+}
+
+#[test]
+fn registry_write() {
+    use registry::HKEY_CURRENT_USER;
+
+    let key = r"Software\wingui_test";
+
+    // Deletes the key, even if an assert fails before the end.
+    struct DeleteKey(&'static str);
+    impl Drop for DeleteKey {
+        fn drop(&mut self) {
+            let _ = registry::delete_key(HKEY_CURRENT_USER, self.0);
+        }
+    }
+
+    let _delete = DeleteKey(key);
+    registry::write_string(HKEY_CURRENT_USER, key, "Text", "Hello registry! 🦀").unwrap();
+    registry::write_u32(HKEY_CURRENT_USER, key, "Number", 0xdeadbeef).unwrap();
+
+    assert_eq!(registry::read_string(HKEY_CURRENT_USER, key, "Text").unwrap(), "Hello registry! 🦀");
+    assert_eq!(registry::read_u32(HKEY_CURRENT_USER, key, "Number").unwrap(), 0xdeadbeef);
+
+    registry::delete_value(HKEY_CURRENT_USER, key, "Number").unwrap();
+    assert!(registry::read_u32(HKEY_CURRENT_USER, key, "Number").is_err());
+
+    registry::delete_key(HKEY_CURRENT_USER, key).unwrap();
+    assert!(registry::read_string(HKEY_CURRENT_USER, key, "Text").is_err());
+}
+
+#[test]
+#[ignore = "opens the default browser"]
+fn shell_execute() {
+    utils::shell_execute(None, "https://example.com", None, utils::SW_SHOWNORMAL).unwrap();
+    assert!(utils::shell_execute(Some("open"), r"C:\surely\not\a\real\file.txt", None, utils::SW_SHOW).is_err());
+}
+
+#[test]
+fn env_get_set() {
+    let name = "WINUTILS_TEST_ENV";
+    let long_value = "x".repeat(1000);
+
+    assert_eq!(utils::get_env_opt(name).unwrap(), None);
+    assert!(utils::get_env(name).is_err());
+
+    utils::set_env(name, "Hello env! 🦀").unwrap();
+    assert_eq!(utils::get_env(name).unwrap(), "Hello env! 🦀");
+
+    utils::set_env(name, &long_value).unwrap();
+    assert_eq!(utils::get_env(name).unwrap(), long_value.as_str());
+
+    utils::set_env(name, "").unwrap();
+    assert_eq!(utils::get_env_opt(name).unwrap(), Some(WideString::default()));
+
+    utils::remove_env(name).unwrap();
+    assert_eq!(utils::get_env_opt(name).unwrap(), None);
+}
+
+#[test]
+fn stopwatch() {
+    use std::time::Duration;
+
+    let mut stopwatch = utils::Stopwatch::start();
+    std::thread::sleep(Duration::from_millis(50));
+
+    let elapsed = stopwatch.restart();
+    // Sleep can take longer on a busy machine, but never shorter.
+    assert!(elapsed >= Duration::from_millis(45), "{:?}", elapsed);
+    assert!(elapsed < Duration::from_millis(1000), "{:?}", elapsed);
+
+    assert!(stopwatch.elapsed() < elapsed);
+}
+
+#[test]
+fn system_metric() {
+    use utils::{system_metric, SystemMetric};
+
+    assert!(system_metric(SystemMetric::CxScreen) > 0);
+    assert!(system_metric(SystemMetric::CyScreen) > 0);
+    assert!(system_metric(SystemMetric::CMonitors) >= 1);
+    assert!(system_metric(SystemMetric::CxVirtualScreen) >= system_metric(SystemMetric::CxScreen));
+}
+
+#[test]
+fn wide_trim() {
+    let mut padded = WideString::from_str_with_size("Hello", 32);
+    padded.trim_nul();
+    assert_eq!(padded.bytes, WideString::from("Hello").bytes);
+
+    let mut no_nul = WideString { bytes: vec![b'a' as u16] };
+    no_nul.trim_nul();
+    assert_eq!(no_nul.bytes, [b'a' as u16, 0]);
+
+    let spaced = WideString::from(" \t Hello world!\u{3000}\r\n");
+    assert_eq!(spaced.trim().bytes, WideString::from("Hello world!").bytes);
+    assert_eq!(WideString::from("   ").trim().bytes, [0]);
+    assert_eq!(WideString::from_str_with_size("  padded  ", 32).trim().bytes, WideString::from("padded").bytes);
+}
+
+#[test]
+fn user_and_computer_info() {
+    let sam = utils::get_user_info(utils::NAME_SAM_COMPATIBLE).unwrap();
+    let user = std::env::var("USERNAME").unwrap();
+    assert!(sam.to_lowercase().ends_with(&format!("\\{}", user.to_lowercase())));
+    assert!(!sam.contains('\0'));
+
+    let host = utils::get_computer_info(utils::COMPUTER_NAME_DNS_HOSTNAME).unwrap();
+    assert!(!host.is_empty());
+    assert!(!host.contains('\0'));
+}
+
+#[test]
+#[ignore = "needs a computer joined to a domain"]
+fn user_info_fully_qualified_dn() {
+    let dn = utils::get_user_info(utils::NAME_FULLY_QUALIFIED_DN).unwrap();
+    assert!(dn.starts_with("CN="));
+    assert!(!dn.contains('\0'));
+}
+
+#[test]
+fn info_signatures() {
+    // Both have the same signature with and without the `winapi-crate` feature. CI runs
+    // the tests in both states.
+    let functions: [fn(u32) -> std::io::Result<String>; 2] = [utils::get_user_info, utils::get_computer_info];
+    assert!(functions[0](utils::NAME_SAM_COMPATIBLE).is_ok());
+    assert!(functions[1](utils::COMPUTER_NAME_NET_BIOS).is_ok());
+}
+
+#[test]
+fn lib_symbol_macro() {
+    use std::ffi::c_void;
+
+    let user32 = utils::Library::load("User32.dll").unwrap();
+    let msgbox = crate::symbol!(user32, fn MessageBoxW(*const c_void, *const u16, *const u16, u32) -> i32);
+    assert!(msgbox.is_valid());
+
+    let kernel32 = utils::Library::load("Kernel32.dll").unwrap();
+    let get_tick_count = crate::symbol!(kernel32, fn GetTickCount() -> u32);
+    assert!(get_tick_count.unwrap()() > 0);
+
+    let missing = crate::symbol!(kernel32, fn NotARealFunction());
+    assert!(!missing.is_valid());
+}
+
+#[test]
+fn lib_is_loaded() {
+    assert!(utils::Library::is_loaded("Kernel32.dll"));
+    assert!(!utils::Library::is_loaded("winutils_not_loaded.dll"));
+    assert!(!utils::Library::is_loaded(""));
+}
+
+#[test]
+fn wide_split() {
+    let path = WideString::from("C:\\Windows;C:\\Windows\\System32;;C:\\Tools");
+    let parts: Vec<String> = path.split(';').iter().map(|part| part.to_string()).collect();
+    assert_eq!(parts, ["C:\\Windows", "C:\\Windows\\System32", "", "C:\\Tools"]);
+    assert_eq!(WideString::from("a\u{1F600}b").split('\u{1F600}'), [WideString::from("a"), WideString::from("b")]);
+
+    let multi = WideString { bytes: "first\0second\0third\0\0".encode_utf16().collect() };
+    assert_eq!(multi.split_nul(), [WideString::from("first"), WideString::from("second"), WideString::from("third")]);
+    assert!(WideString { bytes: vec![0, 0] }.split_nul().is_empty());
+}
+
+#[test]
+fn multi_string() {
+    use crate::wstring::MultiString;
+
+    let list: MultiString = ["first", "", "second", "third"].into_iter().collect();
+    assert_eq!(list.bytes, "first\0second\0third\0\0".encode_utf16().collect::<Vec<u16>>());
+
+    let parsed = unsafe { MultiString::from_raw_ptr(list.ptr()) };
+    assert_eq!(parsed, list);
+    assert_eq!(parsed.to_vec(), [WideString::from("first"), WideString::from("second"), WideString::from("third")]);
+
+    let empty: MultiString = std::iter::empty::<&str>().collect();
+    assert_eq!(empty.bytes, [0, 0]);
+    assert_eq!(unsafe { MultiString::from_raw_ptr(empty.ptr()) }, MultiString::default());
+    assert!(empty.to_vec().is_empty());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn wide_serde() {
+    let wide = WideString::from("C:\\Users\\Tést");
+    let json = serde_json::to_string(&wide).unwrap();
+    assert_eq!(json, "\"C:\\\\Users\\\\Tést\"");
+
+    let back: WideString = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, wide);
+
+    // Unpaired surrogates can't be kept in a normal string.
+    let unpaired = WideString { bytes: vec![0xD800, b'a' as u16, 0] };
+    let back: WideString = serde_json::from_str(&serde_json::to_string(&unpaired).unwrap()).unwrap();
+    assert_eq!(back, "\u{FFFD}a");
+
+    // Neither the missing null-byte of `empty`, nor the padding ends up in the string.
+    assert_eq!(WideString::empty().to_string(), "");
+    assert_eq!(serde_json::to_string(&WideString::empty()).unwrap(), "\"\"");
+    assert_eq!(serde_json::to_string(&WideString::from_str_with_size("Hi", 8)).unwrap(), "\"Hi\"");
+}
+
+#[test]
+fn wide_hash_ord() {
+    use std::collections::{BTreeSet, HashSet};
+
+    let mut set = HashSet::new();
+    set.insert(WideString::from("C:\\Windows"));
+    set.insert(WideString::from_str_with_size("C:\\Windows", 260));
+    assert_eq!(set.len(), 1);
+
+    let sorted: BTreeSet<WideString> = ["b", "ab", "a"].into_iter().map(WideString::from).collect();
+    let sorted: Vec<String> = sorted.iter().map(WideString::to_string).collect();
+    assert_eq!(sorted, ["a", "ab", "b"]);
+    assert_eq!(WideString::from_str_with_size("a", 8).cmp(&WideString::from("a")), std::cmp::Ordering::Equal);
+}
+
+#[test]
+fn wide_from_os_str() {
+    use std::{ffi::{OsStr, OsString}, os::windows::ffi::{OsStrExt, OsStringExt}, path::Path};
+
+    // A lone surrogate can't be in a `&str`, but it can be in a file name.
+    let name = OsString::from_wide(&[b'a' as u16, 0xD800, b'b' as u16]);
+    let expected: Vec<u16> = name.encode_wide().chain(std::iter::once(0)).collect();
+
+    assert_eq!(WideString::from(&name).bytes, expected);
+    assert_eq!(WideString::from(name.as_os_str()).bytes, expected);
+    assert_eq!(WideString::from(Path::new(&name)).bytes, expected);
+    assert_eq!(WideString::from(OsStr::new("C:\\Windows")), WideString::from("C:\\Windows"));
+}
+
+#[test]
+fn wide_to_os_string() {
+    use std::{ffi::OsString, os::windows::ffi::OsStringExt, path::Path};
+
+    let name = OsString::from_wide(&[b'a' as u16, 0xDC00, b'b' as u16]);
+    let wide = WideString::from(&name);
+    assert_eq!(wide.to_os_string(), name);
+    assert_eq!(wide.to_path_buf(), Path::new(&name));
+
+    let padded = WideString::from_str_with_size("C:\\Windows", 260);
+    assert_eq!(padded.to_path_buf(), Path::new("C:\\Windows"));
+}
+
+#[test]
+fn wide_push_str_reserve() {
+    let mut wide = WideString::from("a");
+    wide.push_str("\u{1F600}\u{1F601}\u{1F602}");
+    assert_eq!(wide.bytes.len(), 1 + 6 + 1);
+    assert_eq!(wide.len(), 7);
+    assert_eq!(wide.to_string(), "a\u{1F600}\u{1F601}\u{1F602}");
+
+    let mut padded = WideString::from_str_with_size("a", 16);
+    padded.push_str("b");
+    assert_eq!(padded.bytes, WideString::from("ab").bytes);
+
+    let mut empty = WideString::empty();
+    empty.push_str("c");
+    assert_eq!(empty.bytes, [b'c' as u16, 0]);
+}
+
+#[test]
+fn wide_try_from_slice() {
+    use crate::wstring::Utf16Error;
+
+    let valid: Vec<u16> = "Hi \u{1F600}\0".encode_utf16().collect();
+    assert_eq!(WideString::try_from_slice(&valid).unwrap(), WideString::from("Hi \u{1F600}"));
+
+    let high = WideString::try_from_slice(&[b'a' as u16, 0xD83D, b'b' as u16]).unwrap_err();
+    assert_eq!(high.index(), 1);
+
+    let low: Result<WideString, Utf16Error> = WideString::try_from_slice(&[0xD83D, 0xDE00, 0xDE00]);
+    assert_eq!(low.unwrap_err().index(), 2);
+
+    // Only the part before the null-byte is checked.
+    assert!(WideString::try_from_slice(&[b'a' as u16, 0, 0xD800]).is_ok());
+}
+
+#[test]
+fn lib_functions() {
+    let kernel32 = utils::Library::get_static_lib("Kernel32.dll").unwrap();
+    let functions = kernel32.functions().unwrap();
+    assert!(functions.iter().any(|name| name == "GetProcAddress"));
+
+    assert!(utils::Library::empty().functions().is_err());
+}
+
+#[test]
+#[ignore = "plays sounds"]
+fn beeps() {
+    utils::beep(750, 100).unwrap();
+    utils::message_beep(utils::MB_ICONINFORMATION).unwrap();
+    utils::message_beep(utils::MB_SIMPLE_BEEP).unwrap();
+}
+
+#[test]
+fn beep_frequency() {
+    assert_eq!(utils::beep(20, 100).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    assert_eq!(utils::beep(40_000, 100).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn wide_capacity() {
+    let mut wide = WideString::from("Hello");
+    wide.reserve(100);
+    assert!(wide.capacity() >= wide.bytes.len() + 100);
+    assert_eq!(wide.len(), 5);
+
+    wide.shrink_to_fit();
+    assert!(wide.capacity() < 100);
+    assert_eq!(wide, "Hello");
+}
+
+#[test]
+fn wide_str_chars() {
+    let wide = WideString::from("a\u{E9}\u{1F600}b");
+    let wstr = wstring::WideStr::from(&wide);
+
+    assert_eq!(wstr.chars().collect::<Vec<char>>(), ['a', '\u{E9}', '\u{1F600}', 'b']);
+    assert_eq!(wstr.to_string_lossy(), "a\u{E9}\u{1F600}b");
+
+    let units = [b'a' as u16, 0xD800];
+    let invalid = wstring::WideStr::from_slice(&units);
+    assert_eq!(invalid.chars().last(), Some(char::REPLACEMENT_CHARACTER));
+}
+
+#[test]
+fn ole_init() {
+    // A new thread, so COM isn't initialized as multi-threaded by another test.
+    std::thread::spawn(|| {
+        let ole = init::OleInit::init().unwrap();
+        assert!(init::ComInit::init_mta().is_err());
+        drop(ole);
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn apartment() {
+    use init::Apartment;
+
+    std::thread::spawn(|| {
+        let _com = init::ComInit::init_mta().unwrap();
+        assert_eq!(init::current_apartment().unwrap(), Apartment::Mta);
+    })
+    .join()
+    .unwrap();
+
+    std::thread::spawn(|| {
+        let _com = init::ComInit::init_sta().unwrap();
+        assert_eq!(init::current_apartment().unwrap(), Apartment::Sta);
+    })
+    .join()
+    .unwrap();
+}
+
+#[test]
+fn current_directory() {
+    let _lock = lock_directories();
+    let original = utils::current_directory().unwrap();
+    assert_eq!(original.to_path_buf(), std::env::current_dir().unwrap());
+
+    // Restores the directory for the other tests, even if an assert fails.
+    struct RestoreDirectory(String);
+    impl Drop for RestoreDirectory {
+        fn drop(&mut self) {
+            let _ = utils::set_current_directory(&self.0);
+        }
+    }
+
+    let _restore = RestoreDirectory(original.to_string());
+    let temp = std::env::temp_dir();
+    utils::set_current_directory(temp.to_str().unwrap()).unwrap();
+    assert_eq!(utils::current_directory().unwrap().to_path_buf(), std::env::current_dir().unwrap());
+
+    utils::set_current_directory(&original.to_string()).unwrap();
+    assert_eq!(utils::current_directory().unwrap(), original);
+    assert!(utils::set_current_directory("C:\\winutils\\not\\a\\directory").is_err());
+}
+
+#[test]
+fn current_exe_path() {
+    let path = utils::current_exe_path().unwrap();
+    assert!(path.to_string().to_lowercase().ends_with(".exe"));
+    assert_eq!(path.to_path_buf(), std::env::current_exe().unwrap());
+}
+
+#[test]
+fn wide_replace() {
+    let path = WideString::from("C:/Windows/System32");
+    assert_eq!(path.replace("/", "\\"), "C:\\Windows\\System32");
+    assert_eq!(WideString::from("Hello world").replace("world", "there"), "Hello there");
+
+    // The matches don't overlap.
+    assert_eq!(WideString::from("aaaa").replace("aa", "b"), "bb");
+    assert_eq!(WideString::from("aaa").replace("aa", "b"), "ba");
+
+    assert_eq!(WideString::from("Bl\u{E5}b\u{E6}rgr\u{F8}d \u{1F600}").replace("\u{E6}", "ae").replace("\u{1F600}", ":)"), "Bl\u{E5}baergr\u{F8}d :)");
+    assert_eq!(WideString::from_str_with_size("abc", 16).replace("", "x").bytes, WideString::from("abc").bytes);
+}
+
+#[test]
+fn mb_result() {
+    use utils::MBResult;
+
+    for result in [MBResult::Ok, MBResult::Yes, MBResult::Retry, MBResult::TryAgain, MBResult::Continue] {
+        assert!(result.is_affirmative());
+        assert!(bool::from(result));
+        assert!(!result.is_cancel());
+    }
+
+    for result in [MBResult::Error, MBResult::Cancel, MBResult::Abort, MBResult::Ignore, MBResult::No] {
+        assert!(!result.is_affirmative());
+        assert!(!bool::from(result));
+    }
+
+    assert!(MBResult::Cancel.is_cancel());
+    assert!(!MBResult::No.is_cancel());
+    assert_eq!(MBResult::TryAgain.to_string(), "Try Again");
+    assert_eq!(MBResult::Ok.to_string(), "OK");
+}
+
+#[test]
+fn post_message() {
+    // A null window posts to the queue of the current thread.
+    assert!(window::post_message(window::WindowHandle::NULL, window::WM_NULL, 0, 0).is_ok());
+    assert!(window::post_message(window::WindowHandle(1 as window::HWND), window::WM_NULL, 0, 0).is_err());
+}
+
+#[test]
+fn window_text() {
+    struct Edit;
+    impl window::Windowing for Edit {}
+
+    // The predefined classes don't use the state.
+    let h_wnd = unsafe { window::Windowing::create_window(&mut Edit, "EDIT", "", 200, 100) };
+    assert_eq!(window::get_window_text(h_wnd).unwrap(), "");
+
+    window::set_window_text(h_wnd, "Hello \u{1F600}").unwrap();
+    assert_eq!(window::get_window_text(h_wnd).unwrap(), "Hello \u{1F600}");
+
+    assert!(window::get_window_text(window::WindowHandle::NULL).is_err());
+    assert!(window::set_window_text(window::WindowHandle::NULL, "Nope").is_err());
+}
+
+#[test]
+fn rect_helpers() {
+    use window::{POINT, RECT};
+
+    let rect = RECT::from_xywh(-20, -10, 40, 30);
+    assert_eq!(rect, RECT { left: -20, top: -10, right: 20, bottom: 20 });
+    assert_eq!((rect.width(), rect.height()), (40, 30));
+    assert_eq!(rect.center(), POINT { x: 0, y: 5 });
+    assert_eq!(RECT::from_xywh(-10, -10, 7, 7).center(), POINT { x: -7, y: -7 });
+
+    assert!(rect.contains(-20, -10));
+    assert!(rect.contains(19, 19));
+    assert!(!rect.contains(20, 0));
+    assert!(!rect.contains(0, -11));
+
+    let mut moved = rect;
+    moved.offset(-5, 10);
+    assert_eq!(moved, RECT::from_xywh(-25, 0, 40, 30));
+
+    let mut grown = rect;
+    grown.inflate(5, -5);
+    assert_eq!(grown, RECT { left: -25, top: -5, right: 25, bottom: 15 });
+}
+
+#[test]
+fn point_helpers() {
+    use window::POINT;
+
+    let a = POINT { x: 10, y: -5 };
+    let b = POINT { x: -3, y: 7 };
+    assert_eq!(a + b, POINT { x: 7, y: 2 });
+    assert_eq!(a - b, POINT { x: 13, y: -12 });
+
+    for point in [POINT { x: 100, y: 200 }, POINT { x: -1, y: -32768 }, POINT { x: 32767, y: -20 }] {
+        assert_eq!(POINT::from_lparam(point.to_lparam()), point);
+    }
+
+    // Like a mouse message 10 pixels left of and 2 pixels above the window.
+    assert_eq!(POINT::from_lparam(0xFFFE_FFF6_u32 as isize), POINT { x: -10, y: -2 });
+    assert_eq!(POINT { x: -10, y: -2 }.to_lparam(), 0xFFFE_FFF6_u32 as isize);
+}
+
+#[test]
+fn word_helpers() {
+    use window::{get_x_lparam, get_y_lparam, hiword, loword};
+
+    assert_eq!(loword(0x1234_ABCD), 0xABCD);
+    assert_eq!(hiword(0x1234_ABCD), 0x1234);
+    assert_eq!(hiword(0xFFFF_0001), 0xFFFF);
+
+    // The high bits of each word are set, so they're negative.
+    let l_param = 0x8000_FFFF_u32 as isize;
+    assert_eq!(get_x_lparam(l_param), -1);
+    assert_eq!(get_y_lparam(l_param), -32768);
+    assert_eq!(get_x_lparam(0x0020_0010), 16);
+    assert_eq!(get_y_lparam(0x0020_0010), 32);
+}
+
+#[test]
+fn thread_timer() {
+    // Without a window, Windows picks the id.
+    let first = window::Timer::set(window::WindowHandle::NULL, 0, 1000).unwrap();
+    let second = window::Timer::set(window::WindowHandle::NULL, 0, 1000).unwrap();
+    assert_ne!(first.id(), 0);
+    assert_ne!(first.id(), second.id());
+}
+
+#[test]
+fn lib_search_directory() {
+    use utils::{Library, LOAD_LIBRARY_SEARCH_DEFAULT_DIRS};
+
+    let _lock = lock_directories();
+
+    let dir = std::env::temp_dir().join("winutils_search_directory");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    // Renamed copies of a system library, so they aren't found anywhere else.
+    let version = utils::expand_env_strings("%SystemRoot%\\System32\\Version.dll").unwrap().to_path_buf();
+    let added = dir.join("winutils_added.dll");
+    let dll_directory = dir.join("winutils_dll_directory.dll");
+    for copy in [&added, &dll_directory] {
+        if !copy.exists() {
+            std::fs::copy(&version, copy).unwrap();
+        }
+    }
+
+    let dir = dir.to_str().unwrap();
+    assert!(Library::load_with_flags("winutils_added.dll", LOAD_LIBRARY_SEARCH_DEFAULT_DIRS).is_err());
+    let search = utils::add_search_directory(dir).unwrap();
+    let library = Library::load_with_flags("winutils_added.dll", LOAD_LIBRARY_SEARCH_DEFAULT_DIRS).unwrap();
+    assert_eq!(library.path().unwrap().to_path_buf().file_name(), added.file_name());
+    assert!(utils::add_search_directory("C:\\winutils\\not\\a\\directory").is_err());
+
+    // Unloaded and removed, it can't be found again.
+    drop(library);
+    search.remove().unwrap();
+    assert!(Library::load_with_flags("winutils_added.dll", LOAD_LIBRARY_SEARCH_DEFAULT_DIRS).is_err());
+
+    // Resets the directory for the other tests, even if an assert fails.
+    struct ResetDllDirectory;
+    impl Drop for ResetDllDirectory {
+        fn drop(&mut self) {
+            let _ = utils::set_dll_directory(None);
+        }
+    }
+
+    let _reset = ResetDllDirectory;
+    utils::set_dll_directory(Some(dir)).unwrap();
+    let library = Library::load("winutils_dll_directory.dll").unwrap();
+    assert_eq!(library.path().unwrap().to_path_buf().file_name(), dll_directory.file_name());
+}
+
+#[test]
+fn lib_borrowed_fn() {
+    type GetTickCountProc = extern "system" fn() -> u32;
+
+    let kernel32 = utils::Library::load("Kernel32.dll").unwrap();
+    let get_tick_count = kernel32.load_func_ref::<GetTickCountProc>("GetTickCount").unwrap();
+    let first = get_tick_count();
+    assert!(get_tick_count() >= first);
+
+    assert!(kernel32.load_func_ref::<GetTickCountProc>("NotARealFunction").is_err());
+}
+
+#[test]
+fn to_wide() {
+    use wstring::{to_wide, to_wide_no_nul, MultiString};
+
+    assert_eq!(to_wide("Hi \u{1F600}"), WideString::from("Hi \u{1F600}").bytes);
+    assert_eq!(to_wide(""), [0]);
+    assert_eq!(to_wide_no_nul("Hi \u{1F600}"), "Hi \u{1F600}".encode_utf16().collect::<Vec<u16>>());
+    assert!(to_wide_no_nul("").is_empty());
+
+    // A `MultiString` is the strings without null-bytes, each followed by one.
+    let mut packed = to_wide_no_nul("one");
+    packed.push(0);
+    packed.extend(to_wide_no_nul("two"));
+    packed.extend([0, 0]);
+    assert_eq!(["one", "two"].into_iter().collect::<MultiString>().bytes, packed);
+}
+
+#[test]
+fn wide_set_len() {
+    // Like an API, that writes `text` to `buffer` and returns the length.
+    fn fill(buffer: *mut u16, text: &str) -> usize {
+        let units: Vec<u16> = text.encode_utf16().collect();
+        unsafe { ptr::copy_nonoverlapping(units.as_ptr(), buffer, units.len()) };
+        units.len()
+    }
+
+    let mut wide = WideString::with_size(64);
+    let len = fill(wide.mut_ptr(), "Hello");
+    unsafe { wide.set_len(len) };
+    assert_eq!(wide.bytes, WideString::from("Hello").bytes);
+    assert_eq!(wide.to_string(), "Hello");
+
+    // An API that writes the null-byte, but leaves old content after it.
+    let mut wide = WideString::from_str_with_size("Hello world", 64);
+    let len = fill(wide.mut_ptr(), "Hi\0");
+    assert_eq!(len, 3);
+    wide.truncate_to_nul();
+    assert_eq!(wide.bytes, WideString::from("Hi").bytes);
+
+    let mut no_nul = WideString { bytes: vec![b'a' as u16] };
+    no_nul.truncate_to_nul();
+    assert_eq!(no_nul.bytes, [b'a' as u16, 0]);
+}
+
+#[test]
+fn com_security() {
+    std::thread::spawn(|| {
+        let com = init::ComInit::init_sta().unwrap();
+
+        // Another test could have used COM in a way, that already initialized the security.
+        match com.init_security() {
+            Ok(()) => {},
+            Err(error) => assert_eq!(error.raw_os_error(), Some(init::RPC_E_TOO_LATE)),
+        }
+
+        // It can only be initialized once.
+        let error = com.init_security().unwrap_err();
+        assert_eq!(error.raw_os_error(), Some(init::RPC_E_TOO_LATE));
+    })
+    .join()
+    .unwrap();
+}
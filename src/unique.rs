@@ -1,29 +1,152 @@
+use ::std::{mem, ops::{Deref, DerefMut}, ptr};
+
 /// Suposed to simular to <unique_ptr> in c++.
-/// 
-/// Thats it. Might be useless... 😅
+///
+/// The pointee is dropped in place, when the `UniquePtr` is dropped. The memory itself
+/// is not freed, since the `UniquePtr` doesn't know how it was allocated. The pointer is
+/// private, so [`from_raw`](Self::from_raw) is the only way to own a non-null pointer.
 #[repr(transparent)]
-pub struct UniquePtr<T>(pub *mut T);
+pub struct UniquePtr<T>(*mut T);
 
 impl<T> UniquePtr<T> {
-    // pub fn from_raw(ptr: *mut T) -> io::Result<Self> {
-    //         if ptr.is_null() {
-    //             return Err(io::Error::new(io::ErrorKind::InvalidInput, "Pointer is null."));
-    //         }
+    /// Takes ownership of `ptr`.
+    ///
+    /// # Safety
+    /// `ptr` must either be null, or point to a valid `T` that nothing else drops.
+    pub unsafe fn from_raw(ptr: *mut T) -> Self {
+        Self(ptr)
+    }
 
-    //         Ok(Self(ptr))
-    // }
+    /// Returns a `UniquePtr` that doesn't own anything.
+    #[inline(always)]
+    pub const fn null() -> Self {
+        Self(ptr::null_mut())
+    }
 
     #[inline(always)]
     pub fn is_null(&self) -> bool {
         self.0.is_null()
     }
+
+    /// Returns the raw pointer, without giving up the ownership.
+    #[inline(always)]
+    pub fn as_ptr(&self) -> *const T {
+        self.0
+    }
+
+    /// Returns the raw mutable pointer, without giving up the ownership.
+    #[inline(always)]
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.0
+    }
+
+    /// Consumes the `UniquePtr` and returns the raw pointer, without dropping the pointee.
+    pub fn into_raw(self) -> *mut T {
+        let ptr = self.0;
+        mem::forget(self);
+        ptr
+    }
+}
+
+/// Dereferencing requires the pointer to be non-null and valid. It panics if it's null.
+impl<T> Deref for UniquePtr<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        assert!(!self.0.is_null(), "Dereferenced a null UniquePtr.");
+        unsafe { &*self.0 }
+    }
+}
+
+impl<T> DerefMut for UniquePtr<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        assert!(!self.0.is_null(), "Dereferenced a null UniquePtr.");
+        unsafe { &mut *self.0 }
+    }
 }
 
 impl<T> Drop for UniquePtr<T> {
     fn drop(&mut self) {
+        if self.0.is_null() {
+            return;
+        }
+
         unsafe {
-            std::ptr::drop_in_place(self.0);
+            ptr::drop_in_place(self.0);
         }
     }
 }
 
+/// Like [`UniquePtr`], but calls a deleter function on drop instead of dropping the
+/// pointee in place. This is for memory the Windows API allocated, that must be freed
+/// with a specific function like `LocalFree` or `CoTaskMemFree`.
+///
+/// ## Example
+/// ```ignore
+/// let path = unsafe {
+///     OwnedPtr::new(raw_path, |ptr| CoTaskMemFree(ptr.cast()))
+/// };
+/// ```
+pub struct OwnedPtr<T> {
+    ptr: *mut T,
+    deleter: fn(*mut T),
+}
+
+impl<T> OwnedPtr<T> {
+    /// Takes ownership of `ptr`, which is given to `deleter` on drop, unless it's null.
+    ///
+    /// # Safety
+    /// `ptr` must either be null, or be valid to free with `deleter`.
+    pub unsafe fn new(ptr: *mut T, deleter: fn(*mut T)) -> Self {
+        Self { ptr, deleter }
+    }
+
+    #[inline(always)]
+    pub fn is_null(&self) -> bool {
+        self.ptr.is_null()
+    }
+
+    /// Returns the raw pointer, without giving up the ownership.
+    #[inline(always)]
+    pub fn as_ptr(&self) -> *const T {
+        self.ptr
+    }
+
+    /// Returns the raw mutable pointer, without giving up the ownership.
+    #[inline(always)]
+    pub fn as_mut_ptr(&mut self) -> *mut T {
+        self.ptr
+    }
+
+    /// Consumes the `OwnedPtr` and returns the raw pointer, without calling the deleter.
+    pub fn into_raw(self) -> *mut T {
+        let ptr = self.ptr;
+        mem::forget(self);
+        ptr
+    }
+}
+
+/// Dereferencing requires the pointer to be non-null and valid. It panics if it's null.
+impl<T> Deref for OwnedPtr<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        assert!(!self.ptr.is_null(), "Dereferenced a null OwnedPtr.");
+        unsafe { &*self.ptr }
+    }
+}
+
+impl<T> DerefMut for OwnedPtr<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        assert!(!self.ptr.is_null(), "Dereferenced a null OwnedPtr.");
+        unsafe { &mut *self.ptr }
+    }
+}
+
+impl<T> Drop for OwnedPtr<T> {
+    fn drop(&mut self) {
+        if !self.ptr.is_null() {
+            (self.deleter)(self.ptr);
+        }
+    }
+}
@@ -1,9 +1,13 @@
 #![allow(dead_code, unused_imports)]
 
-use ::std::{ffi::c_void, io, ptr, mem};
-use crate::get_wide_string;
+use ::std::{ffi::c_void, fmt, io, ptr, mem, sync::atomic::{AtomicI64, Ordering}, time::Duration};
+use crate::wstring::to_wide;
+use crate::guid::GUID;
+use crate::wstring::WideString;
+use crate::window::WindowHandle;
 pub use crate::library::*;
 pub use crate::unique::*;
+pub use crate::buffer::*;
 
 /// Retrieves information about the current user.
 /// The function fails, if you retrieve information, which is not available.
@@ -14,16 +18,7 @@ pub use crate::unique::*;
 ///
 /// to learn about them
 pub fn get_user_info(name_format: u32) -> io::Result<String> {
-    let mut buffer = [0_u16; 260];
-    let mut size = 260;
-
-    unsafe {
-        if GetUserNameExW(name_format, buffer.as_mut_ptr(), &mut size) == 0 {
-            return Err(io::Error::last_os_error());
-        }
-    }
-    
-    Ok(String::from_utf16_lossy(&buffer[..size as usize]))
+    read_name(|buffer, size| unsafe { GetUserNameExW(name_format, buffer, size) != 0 })
 }
 
 /// Retrieves information about the computer.
@@ -35,24 +30,340 @@ pub fn get_user_info(name_format: u32) -> io::Result<String> {
 ///
 /// to learn about them
 pub fn get_computer_info(computer_format: u32) -> io::Result<String> {
-    let mut buffer = [0_u16; 260];
-    let mut size = 260;
+    read_name(|buffer, size| unsafe { GetComputerNameExW(computer_format, buffer, size) != 0 })
+}
+
+/// Calls `get` with a buffer and its size. If the buffer is too small, `get` must set the size
+/// needed (including the null terminator) and fail with `ERROR_MORE_DATA`, which makes it try
+/// again with a larger buffer. On success the size is the length without the null terminator.
+fn read_name<F>(mut get: F) -> io::Result<String>
+where
+    F: FnMut(*mut u16, &mut u32) -> bool,
+{
+    let mut stack = [0_u16; 260];
+    let mut size = stack.len() as u32;
+
+    if get(stack.as_mut_ptr(), &mut size) {
+        return Ok(String::from_utf16_lossy(&stack[..size as usize]));
+    }
+
+    let error = io::Error::last_os_error();
+    if error.raw_os_error() != Some(ERROR_MORE_DATA) {
+        return Err(error);
+    }
+
+    // The name can change between the calls, so it's tried until the buffer is large enough.
+    loop {
+        let mut heap = vec![0_u16; size as usize];
+
+        if get(heap.as_mut_ptr(), &mut size) {
+            heap.truncate(size as usize);
+            return Ok(String::from_utf16_lossy(&heap));
+        }
+
+        let error = io::Error::last_os_error();
+        if error.raw_os_error() != Some(ERROR_MORE_DATA) || size as usize <= heap.len() {
+            return Err(error);
+        }
+    }
+}
+
+/// Returns the path of a known folder, like AppData or Documents.
+///
+/// Some of the posible values for `folder_id` is defined as constants starting with `FOLDERID_`
+///
+/// See more at https://docs.microsoft.com/en-us/windows/win32/shell/knownfolderid
+///
+/// to learn about them
+pub fn get_known_folder(folder_id: &GUID) -> io::Result<WideString> {
+    let mut raw_path = ptr::null_mut();
 
-    #[cfg(not(feature = "winapi-crate"))]
     unsafe {
-        if GetComputerNameExW(computer_format, buffer.as_mut_ptr(), &mut size) == 0 {
+        let result = SHGetKnownFolderPath(folder_id, 0, ptr::null_mut(), &mut raw_path);
+
+        // The path must be freed, even if the function fails.
+        let path = OwnedPtr::new(raw_path, |ptr| CoTaskMemFree(ptr.cast()));
+        if result != 0 {
+            return Err(io::Error::from_raw_os_error(result));
+        }
+
+        Ok(WideString::from_raw_ptr(path.as_ptr()))
+    }
+}
+
+/// Returns the system message for the last error of the calling thread, like [`error_message`]
+/// does with the code from `GetLastError`.
+pub fn last_error_message() -> String {
+    let code = io::Error::last_os_error().raw_os_error().unwrap_or(0);
+    error_message(code as u32)
+}
+
+/// Returns the value of the environment variable `name` of the current process. Unlike
+/// `std::env::var`, values that aren't valid UTF-16 are kept as they are.
+/// Fails if the variable doesn't exist, see [`get_env_opt`].
+pub fn get_env(name: &str) -> io::Result<WideString> {
+    get_env_opt(name)?.ok_or_else(|| io::Error::from_raw_os_error(ERROR_ENVVAR_NOT_FOUND))
+}
+
+/// Like [`get_env`], but returns `None` if the variable doesn't exist.
+pub fn get_env_opt(name: &str) -> io::Result<Option<WideString>> {
+    let w_name = to_wide(name);
+    let mut buffer = WideString::with_size(1);
+
+    loop {
+        unsafe {
+            // An empty value also returns 0, but doesn't set the last error.
+            SetLastError(0);
+            let len = GetEnvironmentVariableW(w_name.as_ptr(), buffer.mut_ptr(), buffer.bytes.len() as u32);
+
+            if len == 0 {
+                let error = io::Error::last_os_error();
+
+                return match error.raw_os_error() {
+                    Some(0) => Ok(Some(WideString::default())),
+                    Some(ERROR_ENVVAR_NOT_FOUND) => Ok(None),
+                    _ => Err(error),
+                };
+            }
+
+            // The buffer was too small, and `len` is the size needed including the null terminator.
+            if len as usize >= buffer.bytes.len() {
+                buffer = WideString::with_size(len as usize);
+                continue;
+            }
+
+            buffer.bytes.truncate(len as usize + 1);
+            return Ok(Some(buffer));
+        }
+    }
+}
+
+/// Sets the environment variable `name` of the current process to `value`.
+pub fn set_env(name: &str, value: &str) -> io::Result<()> {
+    let w_name = to_wide(name);
+    let w_value = to_wide(value);
+
+    unsafe {
+        if SetEnvironmentVariableW(w_name.as_ptr(), w_value.as_ptr()) == 0 {
             return Err(io::Error::last_os_error());
         }
     }
 
-    #[cfg(feature = "winapi-crate")]
+    Ok(())
+}
+
+/// Removes the environment variable `name` from the current process.
+pub fn remove_env(name: &str) -> io::Result<()> {
+    let w_name = to_wide(name);
+
     unsafe {
-        if winapi::um::sysinfoapi::GetComputerNameExW(computer_format, buffer.as_mut_ptr(), &mut size) == 0 {
+        if SetEnvironmentVariableW(w_name.as_ptr(), ptr::null()) == 0 {
             return Err(io::Error::last_os_error());
         }
     }
 
-    Ok(String::from_utf16_lossy(&buffer[..size as usize]))
+    Ok(())
+}
+
+/// Expands the environment variables like `%SystemRoot%` in `input`.
+/// Variables that aren't defined are left as they are.
+pub fn expand_env_strings(input: &str) -> io::Result<WideString> {
+    let w_input = to_wide(input);
+
+    unsafe {
+        // The first call returns the size needed, including the null terminator.
+        let mut size = ExpandEnvironmentStringsW(w_input.as_ptr(), ptr::null_mut(), 0);
+
+        loop {
+            if size == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut buffer = WideString::with_size(size as usize);
+            let needed = ExpandEnvironmentStringsW(w_input.as_ptr(), buffer.mut_ptr(), size);
+
+            // A variable could have changed between the calls, so try again with the new size.
+            if needed > size {
+                size = needed;
+                continue;
+            }
+
+            if needed == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            buffer.bytes.truncate(needed as usize);
+            return Ok(buffer);
+        }
+    }
+}
+
+/// Returns the current directory of the process.
+pub fn current_directory() -> io::Result<WideString> {
+    unsafe {
+        // The first call returns the size needed, including the null terminator.
+        let mut size = GetCurrentDirectoryW(0, ptr::null_mut());
+
+        loop {
+            if size == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut buffer = WideString::with_size(size as usize);
+            let len = GetCurrentDirectoryW(size, buffer.mut_ptr());
+
+            if len == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            // Another thread could have changed it between the calls, so try again with the new size.
+            if len >= size {
+                size = len;
+                continue;
+            }
+
+            // On success the length doesn't include the null terminator.
+            buffer.bytes.truncate(len as usize + 1);
+            return Ok(buffer);
+        }
+    }
+}
+
+/// Returns the full path of the executable of the current process. Unlike
+/// `std::env::current_exe`, paths that aren't valid UTF-16 are kept as they are.
+pub fn current_exe_path() -> io::Result<WideString> {
+    crate::library::module_file_name(ptr::null_mut())
+}
+
+/// Changes the current directory of the process to `path`. This affects every thread.
+pub fn set_current_directory(path: &str) -> io::Result<()> {
+    let w_path = to_wide(path);
+
+    unsafe {
+        if SetCurrentDirectoryW(w_path.as_ptr()) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Opens or runs `file` with the program associated with it, like double-clicking it in the
+/// Explorer does. `file` can also be a URL, which is opened in the default browser.
+///
+/// The common verbs are `open` (the default with `None`), `edit`, `print`, `explore` for folders,
+/// and `runas` to run a program as administrator. `show` is one of the `SW_` values.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::utils::{shell_execute, SW_SHOWNORMAL};
+///
+/// shell_execute(None, "https://example.com", None, SW_SHOWNORMAL).unwrap();
+/// ```
+pub fn shell_execute(verb: Option<&str>, file: &str, params: Option<&str>, show: i32) -> io::Result<()> {
+    let w_verb = verb.map(to_wide);
+    let w_file = to_wide(file);
+    let w_params = params.map(to_wide);
+
+    let result = unsafe {
+        ShellExecuteW(
+            ptr::null_mut(),
+            w_verb.as_ref().map_or(ptr::null(), |verb| verb.as_ptr()),
+            w_file.as_ptr(),
+            w_params.as_ref().map_or(ptr::null(), |params| params.as_ptr()),
+            ptr::null(),
+            show
+        ) as isize
+    };
+
+    // Values above 32 means success. The errors 2, 3, 5 and 11 are the same as the Win32 error codes.
+    match result {
+        33.. => Ok(()),
+        2 | 3 | 5 | 11 => Err(io::Error::from_raw_os_error(result as i32)),
+        0 | 8 => Err(io::Error::new(io::ErrorKind::OutOfMemory, "Out of memory.")),
+        26 => Err(io::Error::other("A sharing violation occurred.")),
+        27 | 31 => Err(io::Error::new(io::ErrorKind::NotFound, "No program is associated with the file.")),
+        28..=30 => Err(io::Error::other("The DDE transaction failed.")),
+        32 => Err(io::Error::new(io::ErrorKind::NotFound, "A DLL was not found.")),
+        _ => Err(io::Error::other(format!("ShellExecuteW failed with {}.", result))),
+    }
+}
+
+/// Returns the text on the clipboard. Fails if the clipboard doesn't contain any text.
+pub fn get_clipboard_text() -> io::Result<WideString> {
+    let _clipboard = Clipboard::open()?;
+
+    unsafe {
+        let handle = GetClipboardData(CF_UNICODETEXT);
+        if handle.is_null() {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "The clipboard contains no text."));
+        }
+
+        let text = GlobalLock(handle) as *const u16;
+        if text.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        let wide = WideString::from_raw_ptr(text);
+        GlobalUnlock(handle);
+
+        Ok(wide)
+    }
+}
+
+/// Replaces the content of the clipboard with `text`.
+pub fn set_clipboard_text(text: &str) -> io::Result<()> {
+    let wtext = to_wide(text);
+
+    unsafe {
+        let handle = GlobalAlloc(GMEM_MOVEABLE, wtext.len() * mem::size_of::<u16>());
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        let buffer = GlobalLock(handle) as *mut u16;
+        if buffer.is_null() {
+            let error = io::Error::last_os_error();
+            GlobalFree(handle);
+            return Err(error);
+        }
+
+        ptr::copy_nonoverlapping(wtext.as_ptr(), buffer, wtext.len());
+        GlobalUnlock(handle);
+
+        let clipboard = Clipboard::open();
+        if clipboard.is_err() || EmptyClipboard() == 0 || SetClipboardData(CF_UNICODETEXT, handle).is_null() {
+            let error = io::Error::last_os_error();
+            // The system only owns the memory, if `SetClipboardData` succeeded.
+            GlobalFree(handle);
+            return Err(clipboard.err().unwrap_or(error));
+        }
+    }
+
+    Ok(())
+}
+
+/// Opens the clipboard and closes it again when dropped, so it's closed on errors too.
+struct Clipboard;
+
+impl Clipboard {
+    fn open() -> io::Result<Self> {
+        unsafe {
+            if OpenClipboard(ptr::null_mut()) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(Self)
+    }
+}
+
+impl Drop for Clipboard {
+    fn drop(&mut self) {
+        unsafe {
+            CloseClipboard();
+        }
+    }
 }
 
 /// A function for showing a `MessageBox`
@@ -60,32 +371,388 @@ pub fn get_computer_info(computer_format: u32) -> io::Result<String> {
 /// For documenttation on `mb_type` values, look at the documentation at
 /// [https://docs.microsoft.com/en-us/windows/win32/api/winuser/nf-winuser-messageboxw]
 pub fn msgbox(text: &str, title: Option<&str>, mb_type: u32) -> MBResult {
-    let wtext = get_wide_string(text);
+    msgbox_owned(WindowHandle::NULL, text, title, mb_type)
+}
+
+/// Like [`msgbox`], but with an owner window. Passing the main window of the application
+/// makes the message box modal to it, so it stays in front and blocks input to the window.
+///
+/// [`WindowHandle::NULL`] as `owner` does the same as [`msgbox`].
+pub fn msgbox_owned(owner: WindowHandle, text: &str, title: Option<&str>, mb_type: u32) -> MBResult {
+    let wtext = to_wide(text);
 
     unsafe {
         match title {
             Some(s) => {
-                let wtitle = get_wide_string(s);
+                let wtitle = to_wide(s);
 
                 #[cfg(not(feature = "winapi-crate"))]
-                return MessageBoxW(ptr::null_mut(), wtext.as_ptr(), wtitle.as_ptr(), mb_type);
+                return MessageBoxW(owner.0, wtext.as_ptr(), wtitle.as_ptr(), mb_type);
 
                 #[cfg(feature = "winapi-crate")]
-                return mem::transmute(winapi::um::winuser::MessageBoxW(ptr::null_mut(), wtext.as_ptr(), wtitle.as_ptr(), mb_type));
+                return mem::transmute::<i32, MBResult>(winapi::um::winuser::MessageBoxW(owner.0.cast(), wtext.as_ptr(), wtitle.as_ptr(), mb_type));
             },
             None => {
                 #[cfg(not(feature = "winapi-crate"))]
-                return MessageBoxW(ptr::null_mut(), wtext.as_ptr(), ptr::null(), mb_type);
+                return MessageBoxW(owner.0, wtext.as_ptr(), ptr::null(), mb_type);
 
                 #[cfg(feature = "winapi-crate")]
-                return mem::transmute(winapi::um::winuser::MessageBoxW(ptr::null_mut(), wtext.as_ptr(), ptr::null(), mb_type));
+                return mem::transmute::<i32, MBResult>(winapi::um::winuser::MessageBoxW(owner.0.cast(), wtext.as_ptr(), ptr::null(), mb_type));
             }
         }
     }
 }
 
+/// A builder for showing a `MessageBox` with typed flags, instead of the raw `mb_type` of [`msgbox`].
+///
+/// ## Example
+/// ```no_run
+/// use winutils::utils::{MessageBox, MessageBoxButtons, MessageBoxIcon, MBResult};
+///
+/// let result = MessageBox::new("Do you want to save?")
+///     .title("My App")
+///     .buttons(MessageBoxButtons::YesNoCancel)
+///     .icon(MessageBoxIcon::Question)
+///     .show();
+///
+/// if result == MBResult::Yes {
+///     // Save...
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct MessageBox {
+    text: String,
+    title: Option<String>,
+    owner: WindowHandle,
+    buttons: MessageBoxButtons,
+    icon: MessageBoxIcon,
+    default_button: MessageBoxDefault,
+}
+
+impl MessageBox {
+    /// Creates a message box showing `text` with an OK button and no icon.
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.to_owned(),
+            title: None,
+            owner: WindowHandle::NULL,
+            buttons: MessageBoxButtons::Ok,
+            icon: MessageBoxIcon::None,
+            default_button: MessageBoxDefault::First,
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_owned());
+        self
+    }
+
+    /// Sets the owner window, which makes the message box modal to it. See [`msgbox_owned`].
+    pub fn owner(mut self, owner: WindowHandle) -> Self {
+        self.owner = owner;
+        self
+    }
+
+    pub fn buttons(mut self, buttons: MessageBoxButtons) -> Self {
+        self.buttons = buttons;
+        self
+    }
+
+    pub fn icon(mut self, icon: MessageBoxIcon) -> Self {
+        self.icon = icon;
+        self
+    }
+
+    /// Sets which of the buttons that is selected when the box is shown.
+    pub fn default_button(mut self, default_button: MessageBoxDefault) -> Self {
+        self.default_button = default_button;
+        self
+    }
+
+    /// Returns the `mb_type` flags that [`MessageBox::show`] passes to `MessageBoxW`.
+    pub fn flags(&self) -> u32 {
+        self.buttons as u32 | self.icon as u32 | self.default_button as u32
+    }
+
+    /// Shows the message box and blocks until it's closed.
+    pub fn show(&self) -> MBResult {
+        msgbox_owned(self.owner, &self.text, self.title.as_deref(), self.flags())
+    }
+}
+
+/// The buttons of a [`MessageBox`]. Maps to the `MB_OK`, `MB_OKCANCEL`, ... values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum MessageBoxButtons {
+    Ok = 0x0,
+    OkCancel = 0x1,
+    AbortRetryIgnore = 0x2,
+    YesNoCancel = 0x3,
+    YesNo = 0x4,
+    RetryCancel = 0x5,
+    CancelTryContinue = 0x6,
+}
+
+/// The icon of a [`MessageBox`]. Maps to the `MB_ICON*` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum MessageBoxIcon {
+    None = 0x0,
+    Error = 0x10,
+    Question = 0x20,
+    Warning = 0x30,
+    Information = 0x40,
+}
+
+/// The default button of a [`MessageBox`]. Maps to the `MB_DEFBUTTON*` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u32)]
+pub enum MessageBoxDefault {
+    First = 0x0,
+    Second = 0x100,
+    Third = 0x200,
+    Fourth = 0x300,
+}
+
+/// Removes as many pages as possible from the working set of the current process.
+///
+/// Windows pages the memory back in when it's used again, so this only lowers the
+/// memory usage shown in e.g. the Task Manager for a while.
+pub fn trim_working_set() -> io::Result<()> {
+    set_working_set_size(usize::MAX, usize::MAX)
+}
+
+/// Sets the minimum and maximum working set size of the current process in bytes.
+/// Passing `usize::MAX` for both, removes as many pages as possible.
+pub fn set_working_set_size(min: usize, max: usize) -> io::Result<()> {
+    unsafe {
+        if SetProcessWorkingSetSize(GetCurrentProcess(), min, max) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns all the environment variables of the current process as `(name, value)`.
+///
+/// The hidden entries Windows uses for the current directory of each drive
+/// (like `=C:=C:\Windows`) are skipped.
+pub fn environment_block() -> io::Result<Vec<(WideString, WideString)>> {
+    unsafe {
+        let block = GetEnvironmentStringsW();
+        if block.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut vars = Vec::new();
+        let mut entry: *const u16 = block;
+
+        loop {
+            let len = (0..).take_while(|&i| *entry.offset(i) != 0).count();
+            if len == 0 {
+                break;
+            }
+
+            let units = std::slice::from_raw_parts(entry, len);
+            if units[0] != b'=' as u16 {
+                if let Some(split) = units.iter().position(|&c| c == b'=' as u16) {
+                    vars.push((WideString::from(&units[..split]), WideString::from(&units[split + 1..])));
+                }
+            }
+
+            entry = entry.add(len + 1);
+        }
+
+        FreeEnvironmentStringsW(block);
+        Ok(vars)
+    }
+}
+
+/// Builds an environment block for e.g. `CreateProcessW` from `(name, value)` pairs.
+/// The block is `NAME=VALUE\0NAME=VALUE\0\0` and the variables are sorted by name
+/// without case, as Windows requires.
+pub fn build_env_block(vars: &[(&str, &str)]) -> Vec<u16> {
+    let mut sorted = vars.to_vec();
+    sorted.sort_by_key(|(name, _)| name.to_uppercase());
+
+    let mut block = Vec::new();
+    for (name, value) in sorted {
+        block.extend(to_wide(&format!("{}={}", name, value)));
+    }
+
+    // An empty block still needs the double null.
+    if block.is_empty() {
+        block.push(0);
+    }
+    block.push(0);
+
+    block
+}
+
+/// Returns a system metric or configuration setting with `GetSystemMetrics`. The sizes are in
+/// pixels, and the settings are nonzero if they're enabled. Returns 0 if it fails.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::utils::{system_metric, SystemMetric};
+///
+/// let width = system_metric(SystemMetric::CxScreen);
+/// let height = system_metric(SystemMetric::CyScreen);
+/// println!("The primary monitor is {}x{}", width, height);
+/// ```
+pub fn system_metric(index: SystemMetric) -> i32 {
+    unsafe { GetSystemMetrics(index as i32) }
+}
+
+/// The `SM_` indices for [`system_metric`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(i32)]
+pub enum SystemMetric {
+    /// The width of the primary monitor.
+    CxScreen = 0,
+    /// The height of the primary monitor.
+    CyScreen = 1,
+    CxVScroll = 2,
+    CyHScroll = 3,
+    /// The height of a caption.
+    CyCaption = 4,
+    CxBorder = 5,
+    CyBorder = 6,
+    CxFixedFrame = 7,
+    CyFixedFrame = 8,
+    CxIcon = 11,
+    CyIcon = 12,
+    CxCursor = 13,
+    CyCursor = 14,
+    CyMenu = 15,
+    /// The width of the client area of a full screen window on the primary monitor.
+    CxFullScreen = 16,
+    /// The height of the client area of a full screen window on the primary monitor.
+    CyFullScreen = 17,
+    /// Nonzero if a mouse is installed.
+    MousePresent = 19,
+    /// Nonzero if the left and right mouse buttons are swapped.
+    SwapButton = 23,
+    CxMin = 28,
+    CyMin = 29,
+    /// The width of the sizing border.
+    CxFrame = 32,
+    /// The height of the sizing border.
+    CyFrame = 33,
+    /// The number of buttons on the mouse, or 0 if no mouse is installed.
+    CMouseButtons = 43,
+    CxSmIcon = 49,
+    CySmIcon = 50,
+    /// The width of the bounding rectangle of all the monitors.
+    CxVirtualScreen = 78,
+    /// The height of the bounding rectangle of all the monitors.
+    CyVirtualScreen = 79,
+    /// The number of monitors.
+    CMonitors = 80,
+    /// Nonzero if the process runs in a remote desktop session.
+    RemoteSession = 0x1000,
+}
+
+/// A high-resolution stopwatch using `QueryPerformanceCounter`.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::utils::Stopwatch;
+///
+/// let stopwatch = Stopwatch::start();
+/// // Do some work...
+/// println!("Took {:?}", stopwatch.elapsed());
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Stopwatch {
+    start: i64,
+}
+
+impl Stopwatch {
+    /// Starts a new stopwatch from now.
+    pub fn start() -> Self {
+        Self { start: performance_counter() }
+    }
+
+    /// Returns the time since the stopwatch was started.
+    pub fn elapsed(&self) -> Duration {
+        ticks_to_duration(performance_counter() - self.start)
+    }
+
+    /// Starts the stopwatch again from now, and returns the time it had elapsed.
+    pub fn restart(&mut self) -> Duration {
+        let now = performance_counter();
+        let elapsed = ticks_to_duration(now - self.start);
+        self.start = now;
+
+        elapsed
+    }
+}
+
+// The frequency is fixed at boot, so it's only queried once. 0 means it isn't queried yet.
+static PERFORMANCE_FREQUENCY: AtomicI64 = AtomicI64::new(0);
+
+fn performance_counter() -> i64 {
+    let mut counter = 0;
+
+    // It can't fail on Windows XP or later.
+    unsafe {
+        QueryPerformanceCounter(&mut counter);
+    }
+
+    counter
+}
+
+fn ticks_to_duration(ticks: i64) -> Duration {
+    let mut frequency = PERFORMANCE_FREQUENCY.load(Ordering::Relaxed);
+
+    if frequency == 0 {
+        unsafe {
+            QueryPerformanceFrequency(&mut frequency);
+        }
+
+        PERFORMANCE_FREQUENCY.store(frequency, Ordering::Relaxed);
+    }
+
+    let ticks = ticks.max(0) as u64;
+    let frequency = frequency as u64;
+
+    // Splitting up the seconds, so the nanoseconds don't overflow.
+    Duration::from_secs(ticks / frequency) + Duration::from_nanos((ticks % frequency) * 1_000_000_000 / frequency)
+}
+
+/// Plays a tone on the speaker. `freq` is in hertz and must be from 37 to 32767, and
+/// `duration_ms` is in milliseconds. It doesn't return before the tone is done.
+pub fn beep(freq: u32, duration_ms: u32) -> io::Result<()> {
+    if !(37..=32767).contains(&freq) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Frequency must be from 37 to 32767 Hz."));
+    }
+
+    match unsafe { Beep(freq, duration_ms) } {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Plays one of the system sounds, like a message box does. `sound_type` is `MB_OK`, one
+/// of the `MB_ICON` values, or `MB_SIMPLE_BEEP`. It returns right away.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::utils::{message_beep, MB_ICONERROR};
+///
+/// message_beep(MB_ICONERROR).unwrap();
+/// ```
+pub fn message_beep(sound_type: u32) -> io::Result<()> {
+    match unsafe { MessageBeep(sound_type) } {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
 /// `MBResult` is the return type of the `msgbox` function
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 #[repr(i32)]
 pub enum MBResult {
     Error = 0,
@@ -100,6 +767,52 @@ pub enum MBResult {
     Continue = 11
 }
 
+impl MBResult {
+    /// Returns `true` if the user chose to go on: `Ok`, `Yes`, `Retry`, `TryAgain` or `Continue`.
+    pub fn is_affirmative(&self) -> bool {
+        matches!(self, Self::Ok | Self::Yes | Self::Retry | Self::TryAgain | Self::Continue)
+    }
+
+    /// Returns `true` if the user chose `Cancel`. Closing a message box, that has a `Cancel`
+    /// button, or pressing Esc also returns `Cancel`.
+    pub fn is_cancel(&self) -> bool {
+        matches!(self, Self::Cancel)
+    }
+}
+
+/// The same as [`MBResult::is_affirmative`].
+impl From<MBResult> for bool {
+    fn from(result: MBResult) -> Self {
+        result.is_affirmative()
+    }
+}
+
+/// Writes the text of the button, the user clicked.
+impl fmt::Display for MBResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::Error => "Error",
+            Self::Ok => "OK",
+            Self::Cancel => "Cancel",
+            Self::Abort => "Abort",
+            Self::Retry => "Retry",
+            Self::Ignore => "Ignore",
+            Self::Yes => "Yes",
+            Self::No => "No",
+            Self::TryAgain => "Try Again",
+            Self::Continue => "Continue",
+        };
+
+        f.write_str(text)
+    }
+}
+
+const ERROR_ENVVAR_NOT_FOUND: i32 = 203;
+const ERROR_MORE_DATA: i32 = 234;
+
+const CF_UNICODETEXT: u32 = 13;
+const GMEM_MOVEABLE: u32 = 0x2;
+
 pub const NAME_UNKNOWN: u32 = 0;
 pub const NAME_FULLY_QUALIFIED_DN: u32 = 1;
 pub const NAME_SAM_COMPATIBLE: u32 = 2;
@@ -113,6 +826,14 @@ pub const NAME_DNS_DOMAIN: u32 = 12;
 pub const NAME_GIVEN_NAME: u32 = 13;
 pub const NAME_SURNAME: u32 = 14;
 
+pub const FOLDERID_ROAMING_APP_DATA: GUID = GUID::from_u128(0x3EB685DB_65F9_4CF6_A03A_E3EF65729F3D);
+pub const FOLDERID_LOCAL_APP_DATA: GUID = GUID::from_u128(0xF1B32785_6FBA_4FCF_9D55_7B8E7F157091);
+pub const FOLDERID_PROGRAM_DATA: GUID = GUID::from_u128(0x62AB5D82_FDC1_4DC3_A9DD_070D1D495D97);
+pub const FOLDERID_DESKTOP: GUID = GUID::from_u128(0xB4BFCC3A_DB2C_424C_B029_7FE99A87C641);
+pub const FOLDERID_DOCUMENTS: GUID = GUID::from_u128(0xFDD39AD0_238F_46AF_ADB4_6C85480369C7);
+pub const FOLDERID_DOWNLOADS: GUID = GUID::from_u128(0x374DE290_123F_4565_9164_39C4925E467B);
+pub const FOLDERID_PROGRAM_FILES: GUID = GUID::from_u128(0x905E63B6_C1BF_494E_B29C_65B732D3D21A);
+
 pub const COMPUTER_NAME_NET_BIOS: u32 = 0;
 pub const COMPUTER_NAME_DNS_HOSTNAME: u32 = 1;
 pub const COMPUTER_NAME_DNS_DOMAIN: u32 = 2;
@@ -123,21 +844,79 @@ pub const COMPUTER_NAME_PHYSICAL_DNS_DOMAIN: u32 = 6;
 pub const COMPUTER_NAME_PHYSICAL_DNS_FULLY_QUALIFIED: u32 = 7;
 pub const COMPUTER_NAME_MAX: u32 = 8;
 
+pub const SW_HIDE: i32 = 0;
+pub const SW_SHOWNORMAL: i32 = 1;
+pub const SW_SHOWMINIMIZED: i32 = 2;
+pub const SW_SHOWMAXIMIZED: i32 = 3;
+pub const SW_SHOW: i32 = 5;
+
+pub const MB_OK: u32 = 0x0;
+pub const MB_ICONERROR: u32 = 0x10;
+pub const MB_ICONQUESTION: u32 = 0x20;
+pub const MB_ICONWARNING: u32 = 0x30;
+pub const MB_ICONINFORMATION: u32 = 0x40;
+pub const MB_SIMPLE_BEEP: u32 = 0xFFFFFFFF;
+
 #[cfg(not(feature = "winapi-crate"))]
 #[link(name = "User32")]
 extern "system" {
     fn MessageBoxW(hWnd: *mut c_void, lpText: *const u16, lpCaption: *const u16, uType: u32) -> MBResult;
 }
 
-#[cfg(not(feature = "winapi-crate"))]
+#[link(name = "User32")]
+extern "system" {
+    fn GetSystemMetrics(nIndex: i32) -> i32;
+    fn MessageBeep(uType: u32) -> i32;
+    fn OpenClipboard(hWndNewOwner: *mut c_void) -> i32;
+    fn CloseClipboard() -> i32;
+    fn EmptyClipboard() -> i32;
+    fn GetClipboardData(uFormat: u32) -> *mut c_void;
+    fn SetClipboardData(uFormat: u32, hMem: *mut c_void) -> *mut c_void;
+}
+
 #[link(name = "Kernel32")]
 extern "system" {
     /* https://docs.microsoft.com/da-dk/windows/win32/api/sysinfoapi/nf-sysinfoapi-getcomputernameexw */
-    fn GetComputerNameExW(NameType: u32, lpBuffer: *const u16, nSize: *mut u32) -> i32;
+    fn GetComputerNameExW(NameType: u32, lpBuffer: *mut u16, nSize: *mut u32) -> i32;
+    fn GlobalAlloc(uFlags: u32, dwBytes: usize) -> *mut c_void;
+    fn GlobalFree(hMem: *mut c_void) -> *mut c_void;
+    fn GlobalLock(hMem: *mut c_void) -> *mut c_void;
+    fn GlobalUnlock(hMem: *mut c_void) -> i32;
+    fn GetCurrentProcess() -> *mut c_void;
+    fn ExpandEnvironmentStringsW(lpSrc: *const u16, lpDst: *mut u16, nSize: u32) -> u32;
+    fn GetEnvironmentVariableW(lpName: *const u16, lpBuffer: *mut u16, nSize: u32) -> u32;
+    fn SetEnvironmentVariableW(lpName: *const u16, lpValue: *const u16) -> i32;
+    fn SetLastError(dwErrCode: u32);
+    fn QueryPerformanceCounter(lpPerformanceCount: *mut i64) -> i32;
+    fn QueryPerformanceFrequency(lpFrequency: *mut i64) -> i32;
+    fn GetEnvironmentStringsW() -> *mut u16;
+    fn FreeEnvironmentStringsW(penv: *mut u16) -> i32;
+    fn SetProcessWorkingSetSize(hProcess: *mut c_void, dwMinimumWorkingSetSize: usize, dwMaximumWorkingSetSize: usize) -> i32;
+    fn Beep(dwFreq: u32, dwDuration: u32) -> i32;
+    fn GetCurrentDirectoryW(nBufferLength: u32, lpBuffer: *mut u16) -> u32;
+    fn SetCurrentDirectoryW(lpPathName: *const u16) -> i32;
 }
 
 #[link(name = "Secur32")]
 extern "system" {
     /* https://docs.microsoft.com/en-us/windows/win32/api/secext/nf-secext-getusernameexW */
-    fn GetUserNameExW(NameFormat: u32, lpNameBuffer: *const u16, nSize: *mut u32) -> i32;
+    fn GetUserNameExW(NameFormat: u32, lpNameBuffer: *mut u16, nSize: *mut u32) -> u8;
+}
+
+#[link(name = "Shell32")]
+extern "system" {
+    fn SHGetKnownFolderPath(rfid: *const GUID, dwFlags: u32, hToken: *mut c_void, ppszPath: *mut *mut u16) -> i32;
+    fn ShellExecuteW(
+        hwnd: *mut c_void,
+        lpOperation: *const u16,
+        lpFile: *const u16,
+        lpParameters: *const u16,
+        lpDirectory: *const u16,
+        nShowCmd: i32
+    ) -> *mut c_void;
+}
+
+#[link(name = "Ole32")]
+extern "system" {
+    fn CoTaskMemFree(pv: *mut c_void);
 }
@@ -1,6 +1,10 @@
-#![allow(dead_code, non_snake_case)]
+#![allow(dead_code, non_snake_case, clippy::upper_case_acronyms)]
 
-use ::std::{ffi::c_void, ptr, mem};
+use ::std::{cell::{Cell, Ref, RefCell, RefMut}, ffi::c_void, fmt, io, ptr::{self, NonNull}, mem, ops::{Add, Sub}};
+use crate::wstring::WideString;
+pub use crate::resource::*;
+
+pub mod dialog;
 
 // W.I.P.
 pub type HWND = *mut c_void;
@@ -9,6 +13,7 @@ pub type HICON = *mut c_void;
 pub type HCURSOR = *mut c_void;
 pub type HBRUSH = *mut c_void;
 pub type HMENU = *mut c_void;
+pub type HACCEL = *mut c_void;
 pub type PWSTR = *const u16;
 pub type WPARAM = usize;
 pub type LPARAM = isize;
@@ -17,24 +22,94 @@ pub type LRESULT = isize;
 type _WNDPROC = unsafe extern "system" fn(h_wnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT;
 type WNDPROC = Option<_WNDPROC>;
 
-const IDI_APPLICATION: PWSTR = 0x7f00 as PWSTR;
-const IDC_ARROW: PWSTR = 0x7f00 as PWSTR;
+// The standard icons and cursors have ids from different tables, so some of them are the same.
+pub const IDI_APPLICATION: u16 = 32512;
+pub const IDI_ERROR: u16 = 32513;
+pub const IDI_QUESTION: u16 = 32514;
+pub const IDI_WARNING: u16 = 32515;
+pub const IDI_INFORMATION: u16 = 32516;
+pub const IDI_WINLOGO: u16 = 32517;
+pub const IDI_SHIELD: u16 = 32518;
+
+pub const IDC_ARROW: u16 = 32512;
+pub const IDC_IBEAM: u16 = 32513;
+pub const IDC_WAIT: u16 = 32514;
+pub const IDC_CROSS: u16 = 32515;
+pub const IDC_UPARROW: u16 = 32516;
+pub const IDC_SIZENWSE: u16 = 32642;
+pub const IDC_SIZENESW: u16 = 32643;
+pub const IDC_SIZEWE: u16 = 32644;
+pub const IDC_SIZENS: u16 = 32645;
+pub const IDC_SIZEALL: u16 = 32646;
+pub const IDC_NO: u16 = 32648;
+pub const IDC_HAND: u16 = 32649;
+pub const IDC_APPSTARTING: u16 = 32650;
+pub const IDC_HELP: u16 = 32651;
+
+pub const FVIRTKEY: u8 = 0x01;
+pub const FSHIFT: u8 = 0x04;
+pub const FCONTROL: u8 = 0x08;
+pub const FALT: u8 = 0x10;
+
+pub const MOD_ALT: u32 = 0x1;
+pub const MOD_CONTROL: u32 = 0x2;
+pub const MOD_SHIFT: u32 = 0x4;
+pub const MOD_WIN: u32 = 0x8;
+pub const MOD_NOREPEAT: u32 = 0x4000;
+
+const WM_SETICON: u32 = 0x80;
+const ICON_SMALL: usize = 0;
+const ICON_BIG: usize = 1;
 
 const CS_VREDRAW: u32 = 1u32;
 const CS_HREDRAW: u32 = 2u32;
 
-const WM_NULL: u32 = 0;
-const WM_CREATE: u32 = 1;
-const WM_DESTROY: u32 = 2;
-const WM_PAINT: u32 = 15;
-const WM_CLOSE: u32 = 16;
-const WM_QUIT: u32 = 18;
-const WM_NOTIFY: u32 = 78;
-const WM_COMMAND: u32 = 273;
-const WM_WTSSESSION_CHANGE: u32 = 689;
-const WM_HOTKEY: u32 = 786;
+pub const WM_NULL: u32 = 0;
+pub const WM_CREATE: u32 = 1;
+pub const WM_DESTROY: u32 = 2;
+pub const WM_SIZE: u32 = 5;
+pub const WM_PAINT: u32 = 15;
+pub const WM_CLOSE: u32 = 16;
+pub const WM_QUIT: u32 = 18;
+pub const WM_COPYDATA: u32 = 74;
+pub const WM_NOTIFY: u32 = 78;
+pub const WM_NCCREATE: u32 = 129;
+pub const WM_NCDESTROY: u32 = 130;
+pub const WM_KEYDOWN: u32 = 256;
+pub const WM_COMMAND: u32 = 273;
+pub const WM_TIMER: u32 = 275;
+pub const WM_MOUSEMOVE: u32 = 512;
+pub const WM_LBUTTONDOWN: u32 = 513;
+pub const WM_WTSSESSION_CHANGE: u32 = 689;
+pub const WM_HOTKEY: u32 = 786;
+
+pub const WS_OVERLAPPED: u32 = 0x00000000;
+pub const WS_MAXIMIZEBOX: u32 = 0x00010000;
+pub const WS_MINIMIZEBOX: u32 = 0x00020000;
+pub const WS_THICKFRAME: u32 = 0x00040000;
+pub const WS_SYSMENU: u32 = 0x00080000;
+pub const WS_BORDER: u32 = 0x00800000;
+pub const WS_CAPTION: u32 = 0x00C00000;
+pub const WS_VISIBLE: u32 = 0x10000000;
+pub const WS_POPUP: u32 = 0x80000000;
+pub const WS_CHILD: u32 = 0x40000000;
+pub const WS_TABSTOP: u32 = 0x00010000;
+pub const WS_OVERLAPPEDWINDOW: u32 = 13565952;
+
+pub const WS_EX_TOPMOST: u32 = 0x00000008;
+pub const WS_EX_TOOLWINDOW: u32 = 0x00000080;
+pub const WS_EX_CLIENTEDGE: u32 = 0x00000200;
+pub const WS_EX_APPWINDOW: u32 = 0x00040000;
 
-const WS_OVERLAPPEDWINDOW: u32 = 13565952;
+const SPI_GETWORKAREA: u32 = 0x30;
+const SM_CXSCREEN: i32 = 0;
+const SM_CYSCREEN: i32 = 1;
+
+const GWLP_USERDATA: i32 = -21;
+
+const BS_PUSHBUTTON: u32 = 0x0;
+const ES_AUTOHSCROLL: u32 = 0x80;
+const SS_LEFT: u32 = 0x0;
 
 const SW_SHOW: i32 = 5;
 const SW_HIDE: i32 = 0;
@@ -57,7 +132,7 @@ struct WNDCLASSEXW {
 }
 
 #[repr(C)]
-#[derive(Default)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct RECT {
     pub left: i32,
     pub top: i32,
@@ -76,17 +151,140 @@ pub struct MSG {
 }
 
 #[repr(C)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct POINT {
     pub x: i32,
     pub y: i32,
 }
 
+#[repr(C)]
+struct CreateStruct {
+    lpCreateParams: *mut c_void,
+    hInstance: HINSTANCE,
+    hMenu: HMENU,
+    hwndParent: HWND,
+    cy: i32,
+    cx: i32,
+    y: i32,
+    x: i32,
+    style: i32,
+    lpszName: PWSTR,
+    lpszClass: PWSTR,
+    dwExStyle: u32,
+}
+
+/// A keyboard accelerator for an [`AcceleratorTable`]. With `FVIRTKEY` in `fVirt`, `key` is
+/// a virtual key code, and the `FSHIFT`, `FCONTROL` and `FALT` modifiers can be added.
+/// `cmd` is sent to `on_command` as the low word of `w_param`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ACCEL {
+    pub fVirt: u8,
+    pub key: u16,
+    pub cmd: u16,
+}
+
+/// The data sent with `WM_COPYDATA`. See [`send_copy_data`].
+#[repr(C)]
+pub struct COPYDATASTRUCT {
+    pub dwData: usize,
+    pub cbData: u32,
+    pub lpData: *mut c_void,
+}
+
 impl Default for MSG {
     fn default() -> Self {
         unsafe { mem::zeroed() }
     }
 }
 
+impl RECT {
+    /// Creates a `RECT` from the top-left corner and the size.
+    pub const fn from_xywh(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            left: x,
+            top: y,
+            right: x + width,
+            bottom: y + height,
+        }
+    }
+
+    #[inline]
+    pub const fn width(&self) -> i32 {
+        self.right - self.left
+    }
+
+    #[inline]
+    pub const fn height(&self) -> i32 {
+        self.bottom - self.top
+    }
+
+    /// Returns `true` if the point is inside. Like `PtInRect`, the right and bottom edges
+    /// are not part of the rectangle.
+    pub const fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+
+    /// Returns the center, rounded towards the top-left corner.
+    pub const fn center(&self) -> POINT {
+        POINT {
+            x: self.left + self.width() / 2,
+            y: self.top + self.height() / 2,
+        }
+    }
+
+    /// Moves the rectangle by `dx` and `dy`, like `OffsetRect`.
+    pub fn offset(&mut self, dx: i32, dy: i32) {
+        self.left += dx;
+        self.right += dx;
+        self.top += dy;
+        self.bottom += dy;
+    }
+
+    /// Grows the rectangle by `dx` on the left and right, and `dy` on the top and bottom,
+    /// like `InflateRect`. Negative values shrink it.
+    pub fn inflate(&mut self, dx: i32, dy: i32) {
+        self.left -= dx;
+        self.right += dx;
+        self.top -= dy;
+        self.bottom += dy;
+    }
+}
+
+impl POINT {
+    /// Gets the signed coordinates from the `l_param` of mouse messages, like
+    /// `GET_X_LPARAM` and `GET_Y_LPARAM`. They're negative left of or above the window,
+    /// e.g. on another monitor.
+    pub const fn from_lparam(l_param: LPARAM) -> Self {
+        Self {
+            x: get_x_lparam(l_param),
+            y: get_y_lparam(l_param),
+        }
+    }
+
+    /// Packs the coordinates into a `LPARAM` like `MAKELPARAM`, which is the inverse of
+    /// [`from_lparam`](Self::from_lparam). The coordinates are truncated to 16 bits.
+    pub const fn to_lparam(&self) -> LPARAM {
+        ((self.y as u16 as u32) << 16 | self.x as u16 as u32) as LPARAM
+    }
+}
+
+impl Add for POINT {
+    type Output = POINT;
+
+    fn add(self, other: POINT) -> POINT {
+        POINT { x: self.x + other.x, y: self.y + other.y }
+    }
+}
+
+impl Sub for POINT {
+    type Output = POINT;
+
+    fn sub(self, other: POINT) -> POINT {
+        POINT { x: self.x - other.x, y: self.y - other.y }
+    }
+}
+
 impl Default for WNDCLASSEXW {
     fn default() -> Self {
         unsafe {
@@ -95,13 +293,41 @@ impl Default for WNDCLASSEXW {
     }
 }
 
+/// A window with the state `T`, which handles the messages of the window.
+///
+/// The window belongs to the thread that creates it, so [`Windowing::create_window`] and
+/// [`Windowing::run`] must be called on the same thread. `Window` isn't `Send` for that reason.
+///
+/// The state is kept on the heap, so the `Window` can be moved after the window is created.
+/// Messages sent to the window while one of the handlers runs, e.g. by creating a child
+/// window in [`Windowing::on_create`], go straight to `DefWindowProcW`. The window is
+/// destroyed when `Window` is dropped.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::window::{self, Window, Windowing};
+///
+/// struct App;
+/// impl Windowing for App {}
+///
+/// fn main() -> std::io::Result<()> {
+///     let mut window = Window::new(App);
+///     window.register("MyAppWindow")?;
+///
+///     let h_wnd = window.create_window("MyAppWindow", "My App", 400, 300);
+///     window::show(h_wnd);
+///     window.run();
+///
+///     Ok(())
+/// }
+/// ```
 #[derive(Debug)]
 pub struct Window<T> {
     h_wnd: HWND,
     h_instance: HINSTANCE,
-    cls: PWSTR,
-    title: PWSTR,
-    child: T,
+    cls: WideString,
+    title: WideString,
+    state: NonNull<RefCell<T>>,
 }
 
 impl<T> Window<T> {
@@ -109,70 +335,779 @@ impl<T> Window<T> {
         Self {
             h_wnd: ptr::null_mut(),
             h_instance: unsafe { GetModuleHandleW(ptr::null_mut()) },
-            cls: ptr::null(),
-            title: ptr::null(),
-            child: data,
+            cls: WideString::default(),
+            title: WideString::default(),
+            state: NonNull::from(Box::leak(Box::new(RefCell::new(data)))),
         }
     }
 
-    pub fn data(&self) -> &T {
-        &self.child
+    /// Borrows the state. Panics if it's called while one of the handlers runs.
+    pub fn data(&self) -> Ref<'_, T> {
+        self.cell().borrow()
     }
 
-    pub fn data_mut(&mut self) -> &mut T {
-        &mut self.child
+    /// Borrows the state mutably. Panics if it's called while one of the handlers runs.
+    pub fn data_mut(&mut self) -> RefMut<'_, T> {
+        self.cell().borrow_mut()
     }
 
-    // pub fn hwnd(&self) -> HWND {
-    //     self.h_wnd
-    // }
+    #[inline(always)]
+    fn cell(&self) -> &RefCell<T> {
+        // The state is only freed, when `Window` is dropped.
+        unsafe { self.state.as_ref() }
+    }
+
+    /// Returns the handle of the window, or null if it isn't created yet.
+    pub fn hwnd(&self) -> WindowHandle {
+        WindowHandle(self.h_wnd)
+    }
 
     pub fn hinstance(&self) -> HINSTANCE {
-        unsafe { GetModuleHandleW(ptr::null_mut()) }
+        self.h_instance
     }
 
+    /// Returns the title the window was created with.
     pub fn title(&self) -> String {
+        self.title.to_string()
+    }
+
+    /// Returns the name of the registered class.
+    pub fn class(&self) -> String {
+        self.cls.to_string()
+    }
+
+}
+
+impl<T: Windowing> Window<T> {
+    /// Creates the window from `builder`, and sends its messages to `T`. The class must be
+    /// registered by this `Window` with [`Windowing::register`], and a `Window` can only
+    /// have one window at a time.
+    pub fn create(&mut self, builder: &WindowBuilder) -> io::Result<WindowHandle> {
+        if self.cls.is_empty() || self.cls != builder.class.as_str() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "The class isn't registered by this window."));
+        }
+
+        if !self.h_wnd.is_null() && unsafe { IsWindow(self.h_wnd) } != 0 {
+            return Err(io::Error::new(io::ErrorKind::AlreadyExists, "The window is already created."));
+        }
+
+        let h_wnd = builder.create(self.h_instance, self.state.as_ptr().cast())?;
+
+        self.h_wnd = h_wnd;
+        self.cls = WideString::from(builder.class.as_str());
+        self.title = WideString::from(builder.title.as_str());
+
+        Ok(WindowHandle(h_wnd))
+    }
+
+    /// Creates a window of the class `class` in the center of the screen and returns its
+    /// handle, which is null if it fails. See [`create`](Self::create).
+    pub fn create_window(&mut self, class: &str, title: &str, width: i32, height: i32) -> WindowHandle {
+        let builder = WindowBuilder::new(class).title(title).size(width, height);
+        self.create(&builder).unwrap_or(WindowHandle::NULL)
+    }
+}
+
+/// Builds a window with the class, style, position and size given.
+/// By default it's a `WS_OVERLAPPEDWINDOW` of 640x480 in the center of the work area
+/// of the primary monitor, which leaves out the taskbar.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::window::{self, Window, WindowBuilder, Windowing};
+///
+/// struct App;
+/// impl Windowing for App {}
+///
+/// fn main() -> std::io::Result<()> {
+///     let mut window = Window::new(App);
+///     window.register("MyAppWindow")?;
+///
+///     let h_wnd = window.create(
+///         &WindowBuilder::new("MyAppWindow")
+///             .title("Fixed size")
+///             .size(400, 300)
+///             .resizable(false)
+///     )?;
+///
+///     window::show(h_wnd);
+///     window.run();
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct WindowBuilder {
+    class: String,
+    title: String,
+    style: u32,
+    ex_style: u32,
+    position: Option<(i32, i32)>,
+    size: (i32, i32),
+    parent: HWND,
+    menu: HMENU,
+    icon: Option<Icon>,
+}
+
+/// Where the icon of a [`WindowBuilder`] comes from.
+#[derive(Debug, Clone, Copy)]
+enum Icon {
+    Resource(u16),
+    System(u16),
+    Handle(HICON),
+}
+
+impl WindowBuilder {
+    pub fn new(class: &str) -> Self {
+        Self {
+            class: class.to_owned(),
+            title: String::new(),
+            style: WS_OVERLAPPEDWINDOW,
+            ex_style: 0,
+            position: None,
+            size: (640, 480),
+            parent: ptr::null_mut(),
+            menu: ptr::null_mut(),
+            icon: None,
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_owned();
+        self
+    }
+
+    /// Sets the `WS_` style flags.
+    pub fn style(mut self, style: u32) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Sets the `WS_EX_` style flags.
+    pub fn ex_style(mut self, ex_style: u32) -> Self {
+        self.ex_style = ex_style;
+        self
+    }
+
+    /// Removes or adds the sizing border and the maximize button.
+    pub fn resizable(mut self, resizable: bool) -> Self {
+        if resizable {
+            self.style |= WS_THICKFRAME | WS_MAXIMIZEBOX;
+        } else {
+            self.style &= !(WS_THICKFRAME | WS_MAXIMIZEBOX);
+        }
+
+        self
+    }
+
+    /// Places the window at `x` and `y` in screen coordinates, or client coordinates of the parent.
+    pub fn position(mut self, x: i32, y: i32) -> Self {
+        self.position = Some((x, y));
+        self
+    }
+
+    /// Places the window in the center of the work area of the primary monitor.
+    pub fn centered(mut self) -> Self {
+        self.position = None;
+        self
+    }
+
+    pub fn size(mut self, width: i32, height: i32) -> Self {
+        self.size = (width, height);
+        self
+    }
+
+    pub fn parent(mut self, parent: WindowHandle) -> Self {
+        self.parent = parent.0;
+        self
+    }
+
+    /// Sets the menu, or the id of the control, if it's a child window.
+    pub fn menu(mut self, menu: HMENU) -> Self {
+        self.menu = menu;
+        self
+    }
+
+    /// Sets the icon to the icon resource `id` of the module, instead of the icon of the class.
+    pub fn icon(mut self, id: u16) -> Self {
+        self.icon = Some(Icon::Resource(id));
+        self
+    }
+
+    /// Sets the icon to one of the standard icons, like [`IDI_INFORMATION`].
+    pub fn system_icon(mut self, id: u16) -> Self {
+        self.icon = Some(Icon::System(id));
+        self
+    }
+
+    /// Sets the icon to an already loaded icon, e.g. from [`load_icon_file`].
+    pub fn icon_handle(mut self, icon: HICON) -> Self {
+        self.icon = Some(Icon::Handle(icon));
+        self
+    }
+
+    /// Returns the style and extended style flags, the window is created with.
+    pub fn flags(&self) -> (u32, u32) {
+        (self.style, self.ex_style)
+    }
+
+    /// Creates the window, and sends its messages to `state`, if the class was registered
+    /// by `W`. [`Window::create`] does the same without `unsafe`.
+    ///
+    /// # Safety
+    /// The same as [`Windowing::create_window`]: `state` must stay in place and unused until
+    /// the window is destroyed, and the class must not be registered by another type or by
+    /// a [`Window`].
+    pub unsafe fn build<W: Windowing>(&self, state: &mut W) -> io::Result<WindowHandle> {
+        self.create(state.hinstance(), state as *mut W as *mut c_void).map(WindowHandle)
+    }
+
+    /// Creates the window with `param` as the parameter of `WM_NCCREATE`, and sets the icon.
+    fn create(&self, h_instance: HINSTANCE, param: *mut c_void) -> io::Result<HWND> {
+        let icon = match self.icon {
+            Some(Icon::Resource(id)) => Some(unsafe { load_icon(h_instance, id)? }),
+            Some(Icon::System(id)) => Some(unsafe { load_icon(ptr::null_mut(), id)? }),
+            Some(Icon::Handle(icon)) => Some(icon),
+            None => None,
+        };
+
+        let h_wnd = create_window(h_instance, self, param);
+        if h_wnd.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        if let Some(icon) = icon {
+            unsafe {
+                SendMessageW(h_wnd, WM_SETICON, ICON_BIG, icon as LPARAM);
+                SendMessageW(h_wnd, WM_SETICON, ICON_SMALL, icon as LPARAM);
+            }
+        }
+
+        Ok(h_wnd)
+    }
+
+    /// Returns the position of the window, which is the center of the work area if it isn't set.
+    fn origin(&self) -> (i32, i32) {
+        if let Some(position) = self.position {
+            return position;
+        }
+
+        let (width, height) = self.size;
+
         unsafe {
-            let len = (0..).take_while(|&i| *self.title.offset(i) != 0).count() + 1;
-            let slice = ::std::slice::from_raw_parts(self.title, len);
+            let mut rect = RECT::default();
+            if SystemParametersInfoW(SPI_GETWORKAREA, 0, &mut rect as *mut RECT as *mut c_void, 0) == 0 {
+                rect.right = GetSystemMetrics(SM_CXSCREEN);
+                rect.bottom = GetSystemMetrics(SM_CYSCREEN);
+            }
 
-            String::from_utf16_lossy(slice)
+            (
+                rect.left + (rect.right - rect.left - width) / 2,
+                rect.top + (rect.bottom - rect.top - height) / 2,
+            )
         }
     }
+}
 
-    pub fn class(&self) -> String {
+impl<T> Drop for Window<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let exists = |h_wnd: HWND| !h_wnd.is_null() && IsWindow(h_wnd) != 0;
+
+            if exists(self.h_wnd) {
+                DestroyWindow(self.h_wnd);
+            }
+
+            // The state is leaked instead, if the window or a handler still uses it.
+            if !exists(self.h_wnd) && self.cell().try_borrow_mut().is_ok() {
+                drop(Box::from_raw(self.state.as_ptr()));
+            }
+        }
+    }
+}
+
+/// A thin wrapper around a `HWND`. It implements `Display`, which shows the
+/// title and class of the window, e.g. `Window["Title" class=MyClass hwnd=0x...]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct WindowHandle(pub HWND);
+
+impl WindowHandle {
+    /// No window, e.g. for [`post_message`] and [`Timer::set`] on the current thread.
+    pub const NULL: Self = Self(ptr::null_mut());
+
+    #[inline(always)]
+    pub fn is_null(self) -> bool {
+        self.0.is_null()
+    }
+}
+
+impl fmt::Display for WindowHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         unsafe {
-            let len = (0..).take_while(|&i| *self.cls.offset(i) != 0).count() + 1;
-            let slice = ::std::slice::from_raw_parts(self.cls, len);
+            if self.0.is_null() || IsWindow(self.0) == 0 {
+                return write!(f, "Window[invalid hwnd={:?}]", self.0);
+            }
+
+            let mut title = vec![0_u16; GetWindowTextLengthW(self.0).max(0) as usize + 1];
+            let title_len = GetWindowTextW(self.0, title.as_mut_ptr(), title.len() as i32);
+
+            write!(
+                f,
+                "Window[{:?} class={} hwnd={:?}]",
+                String::from_utf16_lossy(&title[..title_len.max(0) as usize]),
+                class_name(*self).unwrap_or_default(),
+                self.0
+            )
+        }
+    }
+}
+
+/// Returns the class name of the window. Fails if `h_wnd` isn't a valid window.
+pub fn class_name(h_wnd: WindowHandle) -> io::Result<WideString> {
+    // Class names can't be longer than 256 characters.
+    let mut buffer = [0_u16; 257];
+
+    unsafe {
+        if GetClassNameW(h_wnd.0, buffer.as_mut_ptr(), buffer.len() as i32) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(WideString::from_raw_ptr(buffer.as_ptr()))
+}
+
+/// Returns the text of a window, which is the title for top-level windows, and the text of
+/// controls like buttons and edit fields.
+pub fn get_window_text(h_wnd: WindowHandle) -> io::Result<WideString> {
+    unsafe {
+        // 0 is both an empty text and an error, so the last error tells them apart.
+        SetLastError(0);
+        let len = GetWindowTextLengthW(h_wnd.0);
+
+        if len <= 0 {
+            let error = io::Error::last_os_error();
+            return match error.raw_os_error() {
+                Some(0) => Ok(WideString::default()),
+                _ => Err(error),
+            };
+        }
+
+        // The length can be larger than the text, but not smaller.
+        let mut buffer = WideString::with_size(len as usize + 1);
+        let copied = GetWindowTextW(h_wnd.0, buffer.mut_ptr(), len + 1);
+
+        buffer.bytes.truncate(copied.max(0) as usize);
+        buffer.bytes.push(0);
+
+        Ok(buffer)
+    }
+}
+
+/// Sets the text of a window, which is the title for top-level windows, and the text of
+/// controls like buttons and edit fields.
+pub fn set_window_text(h_wnd: WindowHandle, text: &str) -> io::Result<()> {
+    let w_text = WideString::from(text);
+
+    unsafe {
+        if SetWindowTextW(h_wnd.0, w_text.ptr()) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends `data` to the window `target` with `WM_COPYDATA`, which also works between
+/// processes. `id` is passed on to the receiver, so it can tell different kinds of
+/// data apart. `from` is the sending window and may be [`WindowHandle::NULL`].
+///
+/// Returns the result of the receiving window, where `Windowing` returns 1 when handled.
+pub fn send_copy_data(target: WindowHandle, from: WindowHandle, id: usize, data: &[u8]) -> LRESULT {
+    let cds = COPYDATASTRUCT {
+        dwData: id,
+        cbData: data.len() as u32,
+        lpData: data.as_ptr() as *mut c_void,
+    };
+
+    unsafe { SendMessageW(target.0, WM_COPYDATA, from.0 as WPARAM, &cds as *const COPYDATASTRUCT as LPARAM) }
+}
+
+/// Registers a message id, that is unique to `name`. Calling it with the same `name`
+/// returns the same id in every process of the session, so it can be used for custom
+/// messages between processes. Returns 0 if it fails.
+pub fn register_window_message(name: &str) -> u32 {
+    let w_name = crate::wstring::to_wide(name);
+
+    unsafe { RegisterWindowMessageW(w_name.as_ptr()) }
+}
+
+/// Loads the icon resource `id` from the module `h_instance`, or one of the standard `IDI_`
+/// icons if `h_instance` is null. The icon is shared, so it must not be destroyed.
+///
+/// # Safety
+/// `h_instance` must be null or a module that is loaded, e.g. from [`Windowing::hinstance`].
+pub unsafe fn load_icon(h_instance: HINSTANCE, id: u16) -> io::Result<HICON> {
+    unsafe {
+        let icon = LoadIconW(h_instance, make_int_resource(id));
+        if icon.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(icon)
+    }
+}
+
+/// Loads the cursor resource `id` from the module `h_instance`, or one of the standard `IDC_`
+/// cursors if `h_instance` is null. The cursor is shared, so it must not be destroyed.
+///
+/// # Safety
+/// `h_instance` must be null or a module that is loaded, e.g. from [`Windowing::hinstance`].
+pub unsafe fn load_cursor(h_instance: HINSTANCE, id: u16) -> io::Result<HCURSOR> {
+    unsafe {
+        let cursor = LoadCursorW(h_instance, make_int_resource(id));
+        if cursor.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(cursor)
+    }
+}
+
+/// `LOWORD`, which returns the low 16 bits of e.g. `w_param`. For `WM_COMMAND` it's the id
+/// of the control or menu item.
+#[inline(always)]
+pub const fn loword(value: usize) -> u16 {
+    value as u16
+}
+
+/// `HIWORD`, which returns bit 16 to 31 of e.g. `w_param`. For `WM_COMMAND` it's the
+/// notification code.
+#[inline(always)]
+pub const fn hiword(value: usize) -> u16 {
+    (value >> 16) as u16
+}
+
+/// `GET_X_LPARAM`, which returns the signed x-coordinate of mouse messages. Unlike
+/// [`loword`], it's negative when the high bit is set.
+#[inline(always)]
+pub const fn get_x_lparam(l_param: LPARAM) -> i32 {
+    l_param as i16 as i32
+}
+
+/// `GET_Y_LPARAM`, which returns the signed y-coordinate of mouse messages.
+#[inline(always)]
+pub const fn get_y_lparam(l_param: LPARAM) -> i32 {
+    (l_param >> 16) as i16 as i32
+}
+
+/// `MAKEINTRESOURCEW`, which passes a resource id where a name is expected.
+#[inline(always)]
+fn make_int_resource(id: u16) -> PWSTR {
+    id as usize as PWSTR
+}
+
+/// Loads an icon file (`.ico`) in the default icon size. The caller owns the icon, so use a
+/// [`ResourceCache`] with [`GdiObject::icon_from_file`] instead, to have it destroyed.
+pub fn load_icon_file(path: &str) -> io::Result<HICON> {
+    GdiObject::icon_from_file(&WideString::from(path)).map(|icon| icon.handle())
+}
+
+/// Creates a push button as a child of `parent`. When it's clicked, the parent gets
+/// `WM_COMMAND` with `id` in the low word of `w_param`.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::window::{self, Windowing, LPARAM, LRESULT, WPARAM};
+///
+/// const BUTTON_ID: u16 = 1;
+///
+/// struct App;
+///
+/// impl Windowing for App {
+///     fn on_command(&mut self, w_param: WPARAM, _l_param: LPARAM) -> Option<LRESULT> {
+///         match window::loword(w_param) {
+///             BUTTON_ID => { println!("Clicked"); Some(0) },
+///             _ => None,
+///         }
+///     }
+/// }
+/// # let h_wnd = window::WindowHandle::NULL;
+///
+/// window::create_button(h_wnd, BUTTON_ID, "Click me", 10, 10, 120, 40).unwrap();
+/// ```
+pub fn create_button(parent: WindowHandle, id: u16, text: &str, x: i32, y: i32, w: i32, h: i32) -> io::Result<WindowHandle> {
+    create_control("BUTTON", WS_TABSTOP | BS_PUSHBUTTON, 0, parent, id, text, (x, y, w, h))
+}
+
+/// Creates a single-line text field as a child of `parent`, that scrolls when the text
+/// is longer than the field.
+pub fn create_edit(parent: WindowHandle, id: u16, text: &str, x: i32, y: i32, w: i32, h: i32) -> io::Result<WindowHandle> {
+    create_control("EDIT", WS_TABSTOP | ES_AUTOHSCROLL, WS_EX_CLIENTEDGE, parent, id, text, (x, y, w, h))
+}
+
+/// Creates a label with left aligned text as a child of `parent`.
+pub fn create_static(parent: WindowHandle, id: u16, text: &str, x: i32, y: i32, w: i32, h: i32) -> io::Result<WindowHandle> {
+    create_control("STATIC", SS_LEFT, 0, parent, id, text, (x, y, w, h))
+}
 
-            String::from_utf16_lossy(slice)
+/// Creates a visible child window of one of the predefined classes.
+fn create_control(
+    class: &str,
+    style: u32,
+    ex_style: u32,
+    parent: WindowHandle,
+    id: u16,
+    text: &str,
+    (x, y, w, h): (i32, i32, i32, i32),
+) -> io::Result<WindowHandle> {
+    let w_class = WideString::from(class);
+    let w_text = WideString::from(text);
+
+    unsafe {
+        let h_wnd = CreateWindowExW(
+            ex_style,
+            w_class.ptr(),
+            w_text.ptr(),
+            WS_CHILD | WS_VISIBLE | style,
+            x,
+            y,
+            w,
+            h,
+            parent.0,
+            id as usize as HMENU,
+            GetModuleHandleW(ptr::null_mut()),
+            ptr::null(),
+        );
+
+        if h_wnd.is_null() {
+            return Err(io::Error::last_os_error());
         }
+
+        Ok(WindowHandle(h_wnd))
     }
+}
 
+/// Ends the message loop of the current thread with `PostQuitMessage`, so [`Windowing::run`]
+/// returns `exit_code`. It only affects the thread it's called from, so use [`post_message`]
+/// with e.g. `WM_CLOSE` from other threads.
+pub fn quit(exit_code: i32) {
+    unsafe {
+        PostQuitMessage(exit_code);
+    }
 }
 
-pub fn show(h_wnd: HWND) {
+/// Posts a message to the queue of the thread, that owns `h_wnd`, and returns without waiting
+/// for it to be handled. Unlike `SendMessageW`, it can be used from other threads without
+/// blocking on the UI.
+pub fn post_message(h_wnd: WindowHandle, msg: u32, w_param: WPARAM, l_param: LPARAM) -> io::Result<()> {
     unsafe {
-        ShowWindow(h_wnd, SW_SHOW);
+        if PostMessageW(h_wnd.0, msg, w_param, l_param) == 0 {
+            return Err(io::Error::last_os_error());
+        }
     }
+
+    Ok(())
 }
 
-pub fn hide(h_wnd: HWND) {
+pub fn show(h_wnd: WindowHandle) {
     unsafe {
-        ShowWindow(h_wnd, SW_HIDE);
+        ShowWindow(h_wnd.0, SW_SHOW);
+    }
+}
+
+pub fn hide(h_wnd: WindowHandle) {
+    unsafe {
+        ShowWindow(h_wnd.0, SW_HIDE);
+    }
+}
+
+/// A table of keyboard accelerators for [`Windowing::run_with_accelerators`].
+/// Tables created with [`AcceleratorTable::create`] are destroyed, when dropped.
+#[derive(Debug)]
+pub struct AcceleratorTable {
+    handle: HACCEL,
+    owned: bool,
+}
+
+impl AcceleratorTable {
+    /// Loads the accelerator table resource `id` from the module `h_instance`.
+    ///
+    /// # Safety
+    /// `h_instance` must be null or a module that is loaded, e.g. from [`Windowing::hinstance`].
+    pub unsafe fn load(h_instance: HINSTANCE, id: u16) -> io::Result<Self> {
+        unsafe {
+            let handle = LoadAcceleratorsW(h_instance, make_int_resource(id));
+            if handle.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+
+            // Loaded tables are freed, when the application exits.
+            Ok(Self { handle, owned: false })
+        }
+    }
+
+    /// Creates an accelerator table from `accels`.
+    pub fn create(accels: &[ACCEL]) -> io::Result<Self> {
+        unsafe {
+            let handle = CreateAcceleratorTableW(accels.as_ptr(), accels.len() as i32);
+            if handle.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+
+            Ok(Self { handle, owned: true })
+        }
+    }
+
+    #[inline(always)]
+    pub fn handle(&self) -> HACCEL {
+        self.handle
+    }
+}
+
+impl Drop for AcceleratorTable {
+    fn drop(&mut self) {
+        if self.owned {
+            unsafe {
+                DestroyAcceleratorTable(self.handle);
+            }
+        }
     }
 }
 
+/// A global hotkey, that sends `WM_HOTKEY` to a window when it's pressed, even if the window
+/// doesn't have focus. [`Windowing::on_hotkey`] gets the `id` in `w_param`.
+/// The hotkey is unregistered, when dropped.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::window::{Hotkey, MOD_ALT, MOD_CONTROL};
+/// # let h_wnd = winutils::window::WindowHandle::NULL;
+///
+/// // Ctrl+Alt+K
+/// let hotkey = Hotkey::register(h_wnd, 1, MOD_CONTROL | MOD_ALT, 0x4B).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Hotkey {
+    h_wnd: HWND,
+    id: i32,
+}
+
+impl Hotkey {
+    /// Registers `vk` with the `MOD_` `modifiers` as a hotkey for `h_wnd`. Fails if another
+    /// program already uses the same combination.
+    pub fn register(h_wnd: WindowHandle, id: i32, modifiers: u32, vk: u32) -> io::Result<Self> {
+        unsafe {
+            if RegisterHotKey(h_wnd.0, id, modifiers, vk) == 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+
+        Ok(Self { h_wnd: h_wnd.0, id })
+    }
+
+    #[inline(always)]
+    pub fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+impl Drop for Hotkey {
+    fn drop(&mut self) {
+        unsafe {
+            UnregisterHotKey(self.h_wnd, self.id);
+        }
+    }
+}
+
+/// A timer, that sends `WM_TIMER` to a window every `interval_ms` milliseconds.
+/// [`Windowing::on_timer`] gets the [`id`](Self::id). The timer is stopped, when dropped.
+///
+/// ## Example
+/// ```no_run
+/// use winutils::window::Timer;
+/// # let h_wnd = winutils::window::WindowHandle::NULL;
+///
+/// let timer = Timer::set(h_wnd, 1, 500).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct Timer {
+    h_wnd: HWND,
+    id: usize,
+}
+
+impl Timer {
+    /// Starts a timer for `h_wnd` with `id`. If a timer with the same `id` already exists
+    /// for `h_wnd`, it's replaced.
+    ///
+    /// If `h_wnd` is null, the timer belongs to the thread instead. `id` is then ignored,
+    /// and Windows picks the id, which [`id`](Self::id) returns. `WM_TIMER` is then posted
+    /// to the message loop without a window, so [`Windowing::on_timer`] doesn't get it.
+    pub fn set(h_wnd: WindowHandle, id: usize, interval_ms: u32) -> io::Result<Self> {
+        unsafe {
+            let result = SetTimer(h_wnd.0, id, interval_ms, ptr::null());
+            if result == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let id = if h_wnd.is_null() { result } else { id };
+            Ok(Self { h_wnd: h_wnd.0, id })
+        }
+    }
+
+    #[inline(always)]
+    pub fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        unsafe {
+            KillTimer(self.h_wnd, self.id);
+        }
+    }
+}
+
+/// The message handlers return `Some` with the result of the message, when they handle it,
+/// or `None` to let `DefWindowProcW` handle it. Most messages should return `Some(0)` when
+/// they're handled.
 #[allow(unused_variables)]
 pub trait Windowing {
-    fn on_create(&mut self, w_param: WPARAM, l_param: LPARAM) {}
-    fn on_command(&mut self, w_param: WPARAM, l_param: LPARAM) {}
-    fn on_draw(&mut self, w_param: WPARAM, l_param: LPARAM) {}
-    fn on_close(&mut self, w_param: WPARAM, l_param: LPARAM) {}
-    fn on_destroy(&mut self, w_param: WPARAM, l_param: LPARAM) {}
-    fn on_hotkey(&mut self, w_param: WPARAM, l_param: LPARAM) {}
-    fn on_notify(&mut self, w_param: WPARAM, l_param: LPARAM) {}
-    fn on_session_change(&mut self, w_param: WPARAM, l_param: LPARAM) {}
+    /// Return `Some(0)` to continue the creation of the window, or `Some(-1)` to abort it.
+    fn on_create(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> { None }
+    fn on_command(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> { None }
+    fn on_draw(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> { None }
+    /// The default destroys the window.
+    fn on_close(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> { None }
+
+    /// The default ends the message loop with [`quit(0)`](quit).
+    fn on_destroy(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        quit(0);
+
+        Some(0)
+    }
+
+    /// `w_param` is the id of the [`Hotkey`], that was pressed.
+    fn on_hotkey(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> { None }
+    fn on_notify(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> { None }
+    fn on_session_change(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> { None }
+
+    /// Called with the new size of the client area.
+    fn on_size(&mut self, width: i32, height: i32) -> Option<LRESULT> { None }
+    /// Called with the id of the [`Timer`] that elapsed.
+    fn on_timer(&mut self, id: usize) -> Option<LRESULT> { None }
+    /// Called with the cursor position in client coordinates, and the `MK_` flags of the
+    /// mouse buttons and keys that are down.
+    fn on_mouse_move(&mut self, x: i32, y: i32, flags: usize) -> Option<LRESULT> { None }
+    /// Called with the cursor position in client coordinates.
+    fn on_left_click(&mut self, x: i32, y: i32) -> Option<LRESULT> { None }
+    /// Called with the virtual key code of the key.
+    fn on_key_down(&mut self, vk: u32) -> Option<LRESULT> { None }
+
+    /// Called with the data another window sent with `WM_COPYDATA`. The data is only
+    /// valid during the call, so copy it if it's needed later.
+    fn on_copy_data(&mut self, id: usize, data: &[u8]) {}
 
     fn run(&mut self) -> WPARAM {
         unsafe {
@@ -191,66 +1126,90 @@ pub trait Windowing {
         }
     }
 
+    /// Like [`Windowing::run`], but translates the keyboard accelerators of `accelerators` to
+    /// `WM_COMMAND` messages first. The commands are sent to `h_wnd`, so the window must be
+    /// created before and is usually the main window.
+    fn run_with_accelerators(&mut self, h_wnd: WindowHandle, accelerators: &AcceleratorTable) -> WPARAM {
+        unsafe {
+            let mut msg = MSG::default();
+
+            while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) != 0 {
+                if msg.message == WM_QUIT {
+                    break;
+                }
+
+                // The message was an accelerator, and is already sent as a command.
+                if TranslateAcceleratorW(h_wnd.0, accelerators.handle, &msg) != 0 {
+                    continue;
+                }
+
+                TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+
+            msg.wParam
+        }
+    }
+
     fn hinstance(&self) -> HINSTANCE {
         unsafe { GetModuleHandleW(ptr::null_mut()) }
     }
 
-    fn register(&mut self, class_name: &str) {
-        unsafe {
-            let cls = crate::get_wide_string(class_name);
-
-            let wc = WNDCLASSEXW {
-                cbSize: mem::size_of::<WNDCLASSEXW>() as u32,
-                style: CS_VREDRAW | CS_HREDRAW,
-                hIcon: LoadIconW(ptr::null_mut(), IDI_APPLICATION),
-                hInconSm: LoadIconW(ptr::null_mut(), IDI_APPLICATION),
-                hCursor: LoadCursorW(ptr::null_mut(), IDC_ARROW),
-                hInstance: self.hinstance(),
-                lpszClassName: cls.as_ptr(),
-                lpfnWndProc: Some(DefWindowProcW),
-                ..Default::default()
-            };
-
-            // if RegisterClassExW(&wc) != 0 {
-            //     self.cls = wc.lpszClassName;
-            // }
-        }
+    /// Registers the window class `class_name` for the windows of this module. The messages
+    /// of the windows created with the class, are sent to [`Windowing::wnd_proc`].
+    fn register(&mut self, class_name: &str) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        let icon = unsafe { LoadIconW(ptr::null_mut(), make_int_resource(IDI_APPLICATION)) };
+        self.register_with_icon(class_name, icon)
     }
 
-    fn create_window(&mut self, class: &str, title: &str, width: i32, height: i32) -> HWND {
-        let wtitle = crate::get_wide_string(title);
-        let cls = crate::get_wide_string(class);
+    /// Like [`Windowing::register`], but the windows of the class get `icon`, e.g. from
+    /// [`load_icon`], instead of the default application icon.
+    fn register_with_icon(&mut self, class_name: &str, icon: HICON) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        register_class(self.hinstance(), class_name, icon, window_proc::<Self>).map(|_| ())
+    }
 
-        unsafe {
-            let (x, y) = {
-                let mut rect = RECT::default();
-                let h_dsk = GetDesktopWindow();
-                GetClientRect(h_dsk, &mut rect);
-        
-                ((rect.right - width) / 2, (rect.bottom - height) / 2)
-            };
+    /// Creates a window of the class `class` in the center of the screen and returns its
+    /// handle, which is null if it fails. The window is hidden until it's shown with [`show`].
+    /// [`Window`] does the same without `unsafe`, because it owns the state.
+    ///
+    /// # Safety
+    /// If `class` was registered by the same type with [`Windowing::register`], the window
+    /// keeps a pointer to `self` to send its messages to. So `self` must stay at the same
+    /// place in memory (e.g. in a `Box` or a local that isn't moved) until the window is
+    /// destroyed, and it must not be used while the window handles a message.
+    ///
+    /// `class` must not be registered by another type, or by a [`Window`], since its
+    /// window procedure would read `self` as the wrong type.
+    unsafe fn create_window(&mut self, class: &str, title: &str, width: i32, height: i32) -> WindowHandle
+    where
+        Self: Sized,
+    {
+        WindowBuilder::new(class)
+            .title(title)
+            .size(width, height)
+            .build(self)
+            .unwrap_or(WindowHandle::NULL)
+    }
 
-            CreateWindowExW(
-                0,
-                cls.as_ptr(),
-                wtitle.as_ptr(),
-                WS_OVERLAPPEDWINDOW,
-                x,
-                y,
-                width,
-                height,
-                ptr::null_mut(),
-                ptr::null_mut(),
-                self.hinstance(),
-                ptr::null()
-            )
+    /// Dispatches `msg` to the handler, and calls `DefWindowProcW` if it isn't handled.
+    fn wnd_proc(&mut self, h_wnd: WindowHandle, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+        match self.handle_message(msg, w_param, l_param) {
+            Some(result) => result,
+            None => unsafe { DefWindowProcW(h_wnd.0, msg, w_param, l_param) },
         }
     }
 
-    fn wnd_proc(&mut self, h_wnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    /// Dispatches `msg` to the handler, and returns `None` if it isn't handled. The window
+    /// procedure calls `DefWindowProcW` after this returns, so the state isn't borrowed
+    /// while `DefWindowProcW` sends more messages, e.g. `WM_DESTROY` for `WM_CLOSE`.
+    fn handle_message(&mut self, msg: u32, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
         unsafe {
-            let mut result = w_param as LRESULT;
-
             match msg {
                 WM_CREATE => self.on_create(w_param, l_param),
                 WM_COMMAND => self.on_command(w_param, l_param),
@@ -260,32 +1219,243 @@ pub trait Windowing {
                 WM_HOTKEY => self.on_hotkey(w_param, l_param),
                 WM_NOTIFY => self.on_notify(w_param, l_param),
                 WM_WTSSESSION_CHANGE => self.on_session_change(w_param, l_param),
-                _ => result = DefWindowProcW(h_wnd, msg, w_param, l_param),
-            };
+                // The width and height are the low and high word of `l_param` (`LOWORD`/`HIWORD`).
+                WM_SIZE => self.on_size(loword(l_param as usize) as i32, hiword(l_param as usize) as i32),
+                WM_TIMER => self.on_timer(w_param),
+                // The coordinates are signed words (`GET_X_LPARAM`/`GET_Y_LPARAM`), since they
+                // can be negative on a multi-monitor setup.
+                WM_MOUSEMOVE => self.on_mouse_move(get_x_lparam(l_param), get_y_lparam(l_param), w_param),
+                WM_LBUTTONDOWN => self.on_left_click(get_x_lparam(l_param), get_y_lparam(l_param)),
+                WM_KEYDOWN => self.on_key_down(w_param as u32),
+                WM_COPYDATA => {
+                    let cds = (l_param as *const COPYDATASTRUCT).as_ref();
+
+                    match cds {
+                        Some(cds) if !cds.lpData.is_null() && cds.cbData > 0 => {
+                            let data = ::std::slice::from_raw_parts(cds.lpData as *const u8, cds.cbData as usize);
+                            self.on_copy_data(cds.dwData, data);
+                        },
+                        Some(cds) => self.on_copy_data(cds.dwData, &[]),
+                        None => (),
+                    }
+
+                    Some(1)
+                },
+                _ => None,
+            }
+        }
+    }
+}
+
+/// Forwards the messages to the state `T`, and keeps track of the class and handle.
+impl<T: Windowing> Windowing for Window<T> {
+    fn on_create(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_create(w_param, l_param)
+    }
+
+    fn on_command(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_command(w_param, l_param)
+    }
+
+    fn on_draw(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_draw(w_param, l_param)
+    }
+
+    fn on_close(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_close(w_param, l_param)
+    }
+
+    fn on_destroy(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_destroy(w_param, l_param)
+    }
+
+    fn on_hotkey(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_hotkey(w_param, l_param)
+    }
+
+    fn on_notify(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_notify(w_param, l_param)
+    }
+
+    fn on_session_change(&mut self, w_param: WPARAM, l_param: LPARAM) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_session_change(w_param, l_param)
+    }
+
+    fn on_size(&mut self, width: i32, height: i32) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_size(width, height)
+    }
+
+    fn on_timer(&mut self, id: usize) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_timer(id)
+    }
 
-            result
+    fn on_mouse_move(&mut self, x: i32, y: i32, flags: usize) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_mouse_move(x, y, flags)
+    }
+
+    fn on_left_click(&mut self, x: i32, y: i32) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_left_click(x, y)
+    }
+
+    fn on_key_down(&mut self, vk: u32) -> Option<LRESULT> {
+        self.cell().borrow_mut().on_key_down(vk)
+    }
+
+    fn on_copy_data(&mut self, id: usize, data: &[u8]) {
+        self.cell().borrow_mut().on_copy_data(id, data)
+    }
+
+    fn hinstance(&self) -> HINSTANCE {
+        self.h_instance
+    }
+
+    fn register_with_icon(&mut self, class_name: &str, icon: HICON) -> io::Result<()> {
+        register_class(self.h_instance, class_name, icon, state_proc::<T>)?;
+        self.cls = WideString::from(class_name);
+
+        Ok(())
+    }
+
+    unsafe fn create_window(&mut self, class: &str, title: &str, width: i32, height: i32) -> WindowHandle {
+        Window::create_window(self, class, title, width, height)
+    }
+}
+
+fn register_class(h_instance: HINSTANCE, class_name: &str, icon: HICON, wnd_proc: _WNDPROC) -> io::Result<u16> {
+    let cls = crate::wstring::to_wide(class_name);
+
+    unsafe {
+        let wc = WNDCLASSEXW {
+            cbSize: mem::size_of::<WNDCLASSEXW>() as u32,
+            style: CS_VREDRAW | CS_HREDRAW,
+            hIcon: icon,
+            hInconSm: icon,
+            hCursor: LoadCursorW(ptr::null_mut(), make_int_resource(IDC_ARROW)),
+            hInstance: h_instance,
+            lpszClassName: cls.as_ptr(),
+            lpfnWndProc: Some(wnd_proc),
+            ..Default::default()
+        };
+
+        // The class name is copied, so `cls` only has to live during the call.
+        let atom = RegisterClassExW(&wc);
+        if atom == 0 {
+            return Err(io::Error::last_os_error());
         }
+
+        Ok(atom)
     }
 }
 
-// impl<T> Windowing for Window<T> {
-//     fn run(&mut self) {
-//         unsafe {
-//             let func = Self::wnd_proc;
-//             SetWindowLongPtrW(self.h_wnd, -4i32 /* GWL_WNDPROC */, func as isize);
+fn create_window(h_instance: HINSTANCE, builder: &WindowBuilder, state: *mut c_void) -> HWND {
+    let wtitle = crate::wstring::to_wide(&builder.title);
+    let cls = crate::wstring::to_wide(&builder.class);
+    let (x, y) = builder.origin();
+    let (width, height) = builder.size;
 
-//             let mut msg = MSG::default();
-//             while GetMessageW(&mut msg, ptr::null_mut(), 0, 0) != 0 {
-//                 if msg.message == WM_QUIT {
-//                     break;
-//                 }
+    unsafe {
+        CreateWindowExW(
+            builder.ex_style,
+            cls.as_ptr(),
+            wtitle.as_ptr(),
+            builder.style,
+            x,
+            y,
+            width,
+            height,
+            builder.parent,
+            builder.menu,
+            h_instance,
+            state
+        )
+    }
+}
 
-//                 TranslateMessage(&msg);
-//                 DispatchMessageW(&msg);
-//             }
-//         }
-//     }
-// }
+/// The state of a window from [`Windowing::create_window`], which is stored in `GWLP_USERDATA`.
+struct Slot<W> {
+    state: *mut W,
+    /// Set while a handler runs, so a message it sends to the window doesn't borrow `state` again.
+    busy: Cell<bool>,
+    /// Set if the window is destroyed while a handler runs, so the slot is freed after it.
+    destroyed: Cell<bool>,
+}
+
+/// The window procedure of classes registered by `W`. The pointer to the state is passed
+/// with `CreateWindowExW`, and is stored in a [`Slot`] when the window is created.
+unsafe extern "system" fn window_proc<W: Windowing>(h_wnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    if msg == WM_NCCREATE {
+        let cs = l_param as *const CreateStruct;
+        let slot = Box::new(Slot {
+            state: (*cs).lpCreateParams as *mut W,
+            busy: Cell::new(false),
+            destroyed: Cell::new(false),
+        });
+
+        SetWindowLongPtrW(h_wnd, GWLP_USERDATA, Box::into_raw(slot) as isize);
+    }
+
+    // A few messages like `WM_GETMINMAXINFO` are sent before `WM_NCCREATE`.
+    let slot = GetWindowLongPtrW(h_wnd, GWLP_USERDATA) as *mut Slot<W>;
+    if slot.is_null() {
+        return DefWindowProcW(h_wnd, msg, w_param, l_param);
+    }
+
+    let mut result = None;
+    if !(*slot).busy.replace(true) {
+        result = (*(*slot).state).handle_message(msg, w_param, l_param);
+        (*slot).busy.set(false);
+
+        // The window was destroyed by the handler.
+        if (*slot).destroyed.get() {
+            drop(Box::from_raw(slot));
+        }
+    }
+
+    // The slot may be freed from here on, if `DefWindowProcW` destroys the window.
+    let result = match result {
+        Some(result) => result,
+        None => DefWindowProcW(h_wnd, msg, w_param, l_param),
+    };
+
+    // The state may be dropped after the window is gone. If a handler is still running,
+    // the slot is freed after it returns.
+    if msg == WM_NCDESTROY {
+        SetWindowLongPtrW(h_wnd, GWLP_USERDATA, 0);
+
+        if (*slot).busy.get() {
+            (*slot).destroyed.set(true);
+        } else {
+            drop(Box::from_raw(slot));
+        }
+    }
+
+    result
+}
+
+/// The window procedure of classes registered by a [`Window`] with the state `T`. The
+/// pointer to the `RefCell` of the `Window` is stored in `GWLP_USERDATA`.
+unsafe extern "system" fn state_proc<T: Windowing>(h_wnd: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> LRESULT {
+    if msg == WM_NCCREATE {
+        let cs = l_param as *const CreateStruct;
+        SetWindowLongPtrW(h_wnd, GWLP_USERDATA, (*cs).lpCreateParams as isize);
+    }
+
+    // It's already borrowed, when a handler sends a message to its own window.
+    let state = GetWindowLongPtrW(h_wnd, GWLP_USERDATA) as *const RefCell<T>;
+    let result = state
+        .as_ref()
+        .and_then(|state| state.try_borrow_mut().ok())
+        .and_then(|mut state| state.handle_message(msg, w_param, l_param));
+
+    if msg == WM_NCDESTROY {
+        SetWindowLongPtrW(h_wnd, GWLP_USERDATA, 0);
+    }
+
+    match result {
+        Some(result) => result,
+        None => DefWindowProcW(h_wnd, msg, w_param, l_param),
+    }
+}
 
 #[link(name = "User32")]
 extern "system" {
@@ -295,18 +1465,34 @@ extern "system" {
     fn LoadCursorW(hinstance: HINSTANCE, lpcursorname: PWSTR) -> HCURSOR;
     fn GetMessageW(lpmsg: *mut MSG, hwnd: HWND, wmsgfiltermin: u32, wmsgfiltermax: u32) -> i32;
     fn TranslateMessage(lpmsg: *const MSG) -> i32;
+    fn TranslateAcceleratorW(hWnd: HWND, hAccTable: HACCEL, lpMsg: *const MSG) -> i32;
+    fn LoadAcceleratorsW(hInstance: HINSTANCE, lpTableName: PWSTR) -> HACCEL;
+    fn CreateAcceleratorTableW(paccel: *const ACCEL, cAccel: i32) -> HACCEL;
+    fn DestroyAcceleratorTable(hAccel: HACCEL) -> i32;
+    fn SetTimer(hWnd: HWND, nIDEvent: usize, uElapse: u32, lpTimerFunc: *const c_void) -> usize;
+    fn KillTimer(hWnd: HWND, uIDEvent: usize) -> i32;
+    fn RegisterHotKey(hWnd: HWND, id: i32, fsModifiers: u32, vk: u32) -> i32;
+    fn UnregisterHotKey(hWnd: HWND, id: i32) -> i32;
     fn DispatchMessageW(lpmsg: *const MSG) -> LRESULT;
-    fn GetDesktopWindow() -> HWND;
+    fn GetSystemMetrics(nIndex: i32) -> i32;
+    fn SystemParametersInfoW(uiAction: u32, uiParam: u32, pvParam: *mut c_void, fWinIni: u32) -> i32;
+    fn IsWindow(hWnd: HWND) -> i32;
+    fn DestroyWindow(hWnd: HWND) -> i32;
+    fn SendMessageW(hWnd: HWND, Msg: u32, wParam: WPARAM, lParam: LPARAM) -> LRESULT;
+    fn RegisterWindowMessageW(lpString: PWSTR) -> u32;
+    fn GetWindowTextLengthW(hWnd: HWND) -> i32;
+    fn GetWindowTextW(hWnd: HWND, lpString: *mut u16, nMaxCount: i32) -> i32;
+    fn SetWindowTextW(hWnd: HWND, lpString: PWSTR) -> i32;
+    fn GetClassNameW(hWnd: HWND, lpClassName: *mut u16, nMaxCount: i32) -> i32;
     fn ShowWindow(hWnd: HWND, nCmdShow: i32) -> i32;
+    fn PostQuitMessage(nExitCode: i32);
+    fn PostMessageW(hWnd: HWND, Msg: u32, wParam: WPARAM, lParam: LPARAM) -> i32;
+    fn GetWindowLongPtrW(hWnd: HWND, nIndex: i32) -> isize;
     fn SetWindowLongPtrW(
         hwnd: HWND,
         nindex: i32,
         dwnewlong: isize
-    ) -> isize;    
-    fn GetClientRect(
-        hwnd: HWND, 
-        lprect: *mut RECT
-    ) -> i32;    
+    ) -> isize;        
     fn CreateWindowExW(
         dwexstyle: u32, 
         lpclassname: PWSTR, 
@@ -327,4 +1513,5 @@ extern "system" {
 #[link(name = "Kernel32")]
 extern "system" {
     fn GetModuleHandleW(lpModuleName: PWSTR) -> HINSTANCE;
+    fn SetLastError(dwErrCode: u32);
 }
\ No newline at end of file
@@ -0,0 +1,149 @@
+//! Modal dialogs from dialog templates in the resources of a module.
+//!
+//! ## Example
+//! ```no_run
+//! use winutils::window::{dialog::{self, DialogProc}, WindowHandle};
+//! use std::ptr;
+//!
+//! struct About;
+//! impl DialogProc for About {}
+//!
+//! fn main() -> std::io::Result<()> {
+//!     // The template 101 must be linked into the executable from a `.rc` file.
+//!     let button = unsafe { dialog::show_dialog(ptr::null_mut(), 101, WindowHandle::NULL, About)? };
+//!     println!("Closed with {}", button);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use ::std::{cell::RefCell, io, ptr};
+use super::{
+    loword, make_int_resource, GetModuleHandleW, GetWindowLongPtrW, IsWindow, SetWindowLongPtrW,
+    WindowHandle, GWLP_USERDATA, HINSTANCE, HWND, LPARAM, WM_CLOSE, WM_COMMAND, WPARAM,
+};
+
+const ERROR_INVALID_WINDOW_HANDLE: i32 = 1400;
+
+type DlgProc = unsafe extern "system" fn(h_dlg: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> isize;
+
+pub const WM_INITDIALOG: u32 = 272;
+
+pub const IDOK: isize = 1;
+pub const IDCANCEL: isize = 2;
+pub const IDABORT: isize = 3;
+pub const IDRETRY: isize = 4;
+pub const IDIGNORE: isize = 5;
+pub const IDYES: isize = 6;
+pub const IDNO: isize = 7;
+
+/// The messages of a dialog, like [`Windowing`](super::Windowing) for windows.
+///
+/// The handlers return `true` when they handle the message, and `false` to let the
+/// dialog manager handle it.
+#[allow(unused_variables)]
+pub trait DialogProc {
+    /// Called before the dialog is shown. Return `true` to give the focus to the first control.
+    fn on_init(&mut self, h_dlg: WindowHandle) -> bool { true }
+
+    /// Called with the id of the control in the low word of `w_param`. The default ends
+    /// the dialog with the id, when it's `IDOK` or `IDCANCEL`.
+    fn on_command(&mut self, h_dlg: WindowHandle, w_param: WPARAM, l_param: LPARAM) -> bool {
+        let id = loword(w_param) as isize;
+
+        if id == IDOK || id == IDCANCEL {
+            return end_dialog(h_dlg, id).is_ok();
+        }
+
+        false
+    }
+
+    /// The default ends the dialog with `IDCANCEL`.
+    fn on_close(&mut self, h_dlg: WindowHandle) -> bool {
+        end_dialog(h_dlg, IDCANCEL).is_ok()
+    }
+
+    /// Dispatches `msg` to the handler.
+    fn dlg_proc(&mut self, h_dlg: WindowHandle, msg: u32, w_param: WPARAM, l_param: LPARAM) -> bool {
+        match msg {
+            WM_INITDIALOG => self.on_init(h_dlg),
+            WM_COMMAND => self.on_command(h_dlg, w_param, l_param),
+            WM_CLOSE => self.on_close(h_dlg),
+            _ => false,
+        }
+    }
+}
+
+/// Shows the dialog template `template_id` from the module `h_instance` (the executable if null),
+/// and sends its messages to `data`. Blocks until the dialog is closed, and returns the result
+/// given to [`end_dialog`].
+///
+/// Messages sent to the dialog while one of the handlers runs, e.g. by setting the text of a
+/// control in [`DialogProc::on_init`], go to the dialog manager.
+///
+/// # Safety
+/// `h_instance` must be null or a module that is loaded, e.g. from
+/// [`Windowing::hinstance`](super::Windowing::hinstance).
+pub unsafe fn show_dialog<T: DialogProc>(h_instance: HINSTANCE, template_id: u16, parent: WindowHandle, data: T) -> io::Result<isize> {
+    let state = Box::new(RefCell::new(data));
+    let h_instance = if h_instance.is_null() { GetModuleHandleW(ptr::null()) } else { h_instance };
+
+    // 0 is also returned for an invalid parent, which can't be told apart from a dialog
+    // ended with 0 afterwards.
+    if !parent.is_null() && IsWindow(parent.0) == 0 {
+        return Err(io::Error::from_raw_os_error(ERROR_INVALID_WINDOW_HANDLE));
+    }
+
+    let result = DialogBoxParamW(
+        h_instance,
+        make_int_resource(template_id),
+        parent.0,
+        dialog_proc::<T>,
+        &*state as *const RefCell<T> as LPARAM
+    );
+
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(result)
+}
+
+/// Closes the dialog `h_dlg`, and makes [`show_dialog`] return `result`.
+pub fn end_dialog(h_dlg: WindowHandle, result: isize) -> io::Result<()> {
+    unsafe {
+        if EndDialog(h_dlg.0, result) == 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// The state is passed with `DialogBoxParamW`, and is stored in `GWLP_USERDATA` on `WM_INITDIALOG`.
+unsafe extern "system" fn dialog_proc<T: DialogProc>(h_dlg: HWND, msg: u32, w_param: WPARAM, l_param: LPARAM) -> isize {
+    if msg == WM_INITDIALOG {
+        SetWindowLongPtrW(h_dlg, GWLP_USERDATA, l_param);
+    }
+
+    // A few messages like `WM_SETFONT` are sent before `WM_INITDIALOG`, and it's already
+    // borrowed, when a handler sends a message to its own dialog.
+    let state = GetWindowLongPtrW(h_dlg, GWLP_USERDATA) as *const RefCell<T>;
+
+    match state.as_ref().and_then(|state| state.try_borrow_mut().ok()) {
+        Some(mut state) => state.dlg_proc(WindowHandle(h_dlg), msg, w_param, l_param) as isize,
+        None => 0,
+    }
+}
+
+#[link(name = "User32")]
+extern "system" {
+    fn DialogBoxParamW(
+        hInstance: HINSTANCE,
+        lpTemplateName: *const u16,
+        hWndParent: HWND,
+        lpDialogFunc: DlgProc,
+        dwInitParam: LPARAM
+    ) -> isize;
+    fn EndDialog(hDlg: HWND, nResult: isize) -> i32;
+}
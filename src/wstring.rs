@@ -4,10 +4,32 @@
 //! 
 //! *HINT!* The structs uses the [`From`] trait a lot.
 
-use crate::get_wide_string;
-use ::std::fmt;
+use ::std::{
+    borrow::Cow,
+    cmp::Ordering,
+    ffi::{OsStr, OsString},
+    fmt,
+    hash::{Hash, Hasher},
+    io,
+    marker::PhantomData,
+    ops::{Add, AddAssign},
+    os::windows::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+};
+
+pub use proc_wstring::{wstr, wstrlen};
+
+/// Encodes `text` as UTF-16 with a null-byte at the end, ready for the Windows API.
+/// The same as `WideString::from(text).bytes`.
+pub fn to_wide(text: &str) -> Vec<u16> {
+    text.encode_utf16().chain(std::iter::once(0)).collect()
+}
 
-pub use proc_wstring::wstr;
+/// Encodes `text` as UTF-16 without the null-byte, e.g. when it's put in the middle of a
+/// larger buffer, like a [`MultiString`].
+pub fn to_wide_no_nul(text: &str) -> Vec<u16> {
+    text.encode_utf16().collect()
+}
 
 /// A struct for making working with unicode-strings easier.
 /// It implements the `Display` trait, so you can always get
@@ -19,67 +41,171 @@ pub use proc_wstring::wstr;
 /// let wstring = WideString::from("Hello world!");
 ///
 /// ```
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[repr(transparent)]
 pub struct WideString {
     pub bytes: Vec<u16>,
 }
 
+/// A borrowed unicode-string. It knows its length, so it doesn't need to scan for the
+/// null-byte every time it's used.
+///
+/// The lifetime ties it to the [`WideString`] or slice it borrows from, so it can't
+/// outlive it. Use [`from_raw_ptr`](Self::from_raw_ptr) for pointers from elsewhere.
+///
+/// ## Examples
+/// ```
+/// use winutils::wstring::{WideStr, WideString};
+///
+/// let wide = WideString::from("Hello world!");
+/// let wstr = WideStr::from(&wide);
+///
+/// assert_eq!(wstr.to_string(), "Hello world!");
+/// ```
+///
+/// A `WideStr` can't be used after its `WideString` is dropped.
+/// ```compile_fail
+/// use winutils::wstring::{WideStr, WideString};
+///
+/// let wstr = {
+///     let wide = WideString::from("Hello world!");
+///     WideStr::from(&wide)
+/// };
+///
+/// println!("{}", wstr);
+/// ```
 #[derive(Clone, Copy)]
-#[repr(transparent)]
-pub struct WideStr {
+pub struct WideStr<'a> {
     ptr: *const u16,
+    len: usize,
+    terminated: bool,
+    _lifetime: PhantomData<&'a [u16]>,
 }
 
-impl From<&WideString> for WideStr {
-    fn from(wide: &WideString) -> Self {
+impl<'a> From<&'a WideString> for WideStr<'a> {
+    fn from(wide: &'a WideString) -> Self {
         Self {
-            ptr: wide.bytes.as_ptr()
+            ptr: wide.bytes.as_ptr(),
+            len: wide.len(),
+            terminated: wide.bytes.contains(&0),
+            _lifetime: PhantomData,
         }
     }
 }
 
-impl From<*const u16> for WideStr {
-    fn from(ptr: *const u16) -> Self {
-        Self { ptr }
+impl<'a> WideStr<'a> {
+    const NULL: Self = Self {
+        ptr: 0 as _,
+        len: 0,
+        terminated: false,
+        _lifetime: PhantomData,
+    };
+
+    /// Creates a `WideStr` from a null-terminated pointer, e.g. returned from the Windows API.
+    /// The length is found once, by scanning for the null-byte.
+    ///
+    /// # Safety
+    /// `ptr` must either be null, or point to a null-terminated string, that's valid for
+    /// as long as the `WideStr` is used. The lifetime is not checked by the compiler.
+    pub unsafe fn from_raw_ptr(ptr: *const u16) -> Self {
+        let len = match ptr.is_null() {
+            true => 0,
+            false => (0..).take_while(|&i| *ptr.offset(i) != 0).count(),
+        };
+
+        Self {
+            ptr,
+            len,
+            terminated: !ptr.is_null(),
+            _lifetime: PhantomData,
+        }
     }
-}
 
-impl From<*mut u16> for WideStr {
-    fn from(ptr: *mut u16) -> Self {
-        Self { ptr: ptr as *const u16 }
+    /// Borrows `slice` up to the first null-byte, or all of it if there's none.
+    /// Unlike a raw pointer, this never reads outside the slice.
+    pub fn from_slice(slice: &'a [u16]) -> Self {
+        let len = slice.iter().position(|&c| c == 0).unwrap_or(slice.len());
+
+        Self {
+            ptr: slice.as_ptr(),
+            len,
+            terminated: len < slice.len(),
+            _lifetime: PhantomData,
+        }
     }
-}
 
-impl WideStr {
-    const NULL: Self = Self { ptr: 0 as _ };
+    /// Creates a `WideStr` of `len` code units at `ptr`, without scanning for the null-byte.
+    ///
+    /// # Safety
+    /// `ptr` must be valid for reading `len` code units, for as long as the `WideStr` is used.
+    pub unsafe fn from_ptr_len(ptr: *const u16, len: usize) -> Self {
+        Self {
+            ptr,
+            len,
+            terminated: false,
+            _lifetime: PhantomData,
+        }
+    }
+
+    /// Returns the amount of code units before the null-byte.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if there's nothing before the null-byte.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
 
     /// Copies the content and takes ownership in a (::crate::wstring::WideString)[`WideString`].
     pub fn to_wide_string(&self) -> WideString {
-        WideString::from_raw_ptr(self.ptr)
+        WideString::from(self.as_slice())
     }
 
-    /// Returns the bytes of the underlying pointer.
-    pub fn as_bytes(&self) -> &[u16] {
-        unsafe {
-            let len = (0..).take_while(|&i| *self.ptr.offset(i) != 0).count() + 1;
-            std::slice::from_raw_parts(self.ptr, len)
+    /// Returns the code units before the null-byte.
+    pub fn as_slice(&self) -> &'a [u16] {
+        match self.ptr.is_null() {
+            true => &[],
+            false => unsafe { std::slice::from_raw_parts(self.ptr, self.len) },
         }
     }
 
-    /// Reads `len` amount of characters of the pointer and returns it as a `String`.
-    pub fn read_part(&self, len: usize) -> String {
-        unsafe {
-            let slice = std::slice::from_raw_parts(self.ptr, len);
-            String::from_utf16_lossy(slice)
+    /// Returns the bytes of the underlying pointer. The null-byte is included, unless
+    /// the `WideStr` is made with [`from_ptr_len`](Self::from_ptr_len) or from a slice
+    /// without one.
+    pub fn as_bytes(&self) -> &'a [u16] {
+        match self.ptr.is_null() {
+            true => &[],
+            false => unsafe { std::slice::from_raw_parts(self.ptr, self.len + self.terminated as usize) },
         }
     }
+
+    /// Decodes the content to a `String`, where invalid UTF-16 is replaced with `U+FFFD`.
+    /// It's always owned, since UTF-16 can't be borrowed as a `str`.
+    pub fn to_string_lossy(&self) -> Cow<'a, str> {
+        Cow::Owned(String::from_utf16_lossy(self.as_slice()))
+    }
+
+    /// Returns an iterator over the characters, without decoding everything first.
+    /// Invalid UTF-16 is replaced with `U+FFFD`.
+    pub fn chars(&self) -> impl Iterator<Item = char> + 'a {
+        char::decode_utf16(self.as_slice().iter().copied()).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+
+    /// Reads `len` amount of characters of the pointer and returns it as a `String`. It
+    /// stops at the end of the string, if `len` is longer.
+    pub fn read_part(&self, len: usize) -> String {
+        let slice = self.as_slice();
+        String::from_utf16_lossy(&slice[..len.min(slice.len())])
+    }
 }
 
 impl From<&str> for WideString {
     fn from(text: &str) -> Self {
         Self {
-            bytes: get_wide_string(text),
+            bytes: to_wide(text),
         }
     }
 }
@@ -90,9 +216,52 @@ impl From<String> for WideString {
     }
 }
 
-impl From<WideStr> for WideString {
-    fn from(text: WideStr) -> Self {
-        Self::from_raw_ptr(text.ptr)
+/// Encodes the `OsStr` directly, so ill-formed UTF-16 (e.g. unpaired surrogates in a
+/// file name) is kept, unlike going through a `&str`.
+impl From<&OsStr> for WideString {
+    fn from(text: &OsStr) -> Self {
+        Self {
+            bytes: text.encode_wide().chain(std::iter::once(0)).collect(),
+        }
+    }
+}
+
+impl From<&OsString> for WideString {
+    fn from(text: &OsString) -> Self {
+        text.as_os_str().into()
+    }
+}
+
+impl From<&Path> for WideString {
+    fn from(path: &Path) -> Self {
+        path.as_os_str().into()
+    }
+}
+
+impl From<WideStr<'_>> for WideString {
+    fn from(text: WideStr<'_>) -> Self {
+        text.to_wide_string()
+    }
+}
+
+/// Copies the code units. Trailing null-bytes are replaced with a single null-byte,
+/// which is added if there isn't one.
+impl From<&[u16]> for WideString {
+    fn from(units: &[u16]) -> Self {
+        units.iter().copied().collect()
+    }
+}
+
+impl FromIterator<u16> for WideString {
+    fn from_iter<I: IntoIterator<Item = u16>>(iter: I) -> Self {
+        let mut bytes: Vec<u16> = iter.into_iter().collect();
+
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        bytes.push(0);
+
+        Self { bytes }
     }
 }
 
@@ -131,12 +300,109 @@ impl WideString {
         self.bytes.as_mut_ptr()
     }
 
+    /// Returns the amount of code units before the null-byte. Padding from e.g.
+    /// [`with_size`](Self::with_size) is not counted, unlike `bytes.len()`.
+    pub fn len(&self) -> usize {
+        self.as_slice().len()
+    }
+
+    /// Returns the amount of code units the buffer can hold without reallocating.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    /// Reserves space for at least `additional` more code units, e.g. before
+    /// many calls to [`push_str`](Self::push_str).
+    #[inline]
+    pub fn reserve(&mut self, additional: usize) {
+        self.bytes.reserve(additional);
+    }
+
+    /// Shrinks the capacity of the buffer as much as possible.
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        self.bytes.shrink_to_fit();
+    }
+
+    /// Returns `true` if there's nothing before the null-byte, or the buffer is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.first().is_none_or(|&c| c == 0)
+    }
+
+    /// Decodes the `WideString` into `out`, which is cleared first. This reuses the
+    /// allocation of `out`, unlike `to_string`. Invalid UTF-16 is replaced with
+    /// `U+FFFD`, just like [`String::from_utf16_lossy`].
+    pub fn decode_into(&self, out: &mut String) {
+        out.clear();
+
+        out.extend(char::decode_utf16(self.iter()).map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)));
+    }
+
+    /// Converts the code units before the null-byte to an `OsString`. Unlike `to_string`,
+    /// this is lossless, so ill-formed UTF-16 like unpaired surrogates is kept.
+    pub fn to_os_string(&self) -> OsString {
+        OsString::from_wide(self.as_slice())
+    }
+
+    /// Converts the code units before the null-byte to a `PathBuf`, without losing anything.
+    pub fn to_path_buf(&self) -> PathBuf {
+        PathBuf::from(self.to_os_string())
+    }
+
+    /// Returns the code units before the null-byte. Padding from e.g.
+    /// [`with_size`](Self::with_size) is not included.
+    pub fn as_slice(&self) -> &[u16] {
+        match self.bytes.iter().position(|&c| c == 0) {
+            Some(nul) => &self.bytes[..nul],
+            None => &self.bytes,
+        }
+    }
+
+    /// Returns the code units up to and including the first null-byte, or all of them
+    /// if there's no null-byte. Padding after the null-byte is not included.
+    pub fn as_slice_with_nul(&self) -> &[u16] {
+        match self.bytes.iter().position(|&c| c == 0) {
+            Some(nul) => &self.bytes[..=nul],
+            None => &self.bytes,
+        }
+    }
+
+    /// Returns an iterator over the code units before the null-byte.
+    pub fn iter(&self) -> impl Iterator<Item = u16> + '_ {
+        self.as_slice().iter().copied()
+    }
+
     /// Returns an empty `WideString`. **Make sure it's not empty before using
     /// it with the Windows API**. If not, then use `Default` instead.
     pub fn empty() -> Self {
         Self { bytes: vec![] }
     }
 
+    /// Copies `slice` up to the first null-byte, like `WideString::from` does with a slice,
+    /// but fails if it's not valid UTF-16. The error has the index of the first unpaired surrogate.
+    ///
+    /// ## Example
+    /// ```
+    /// use winutils::wstring::WideString;
+    ///
+    /// assert!(WideString::try_from_slice(&[0x48, 0x69]).is_ok());
+    /// assert_eq!(WideString::try_from_slice(&[0x48, 0xD800]).unwrap_err().index(), 1);
+    /// ```
+    pub fn try_from_slice(slice: &[u16]) -> Result<Self, Utf16Error> {
+        let units = WideStr::from_slice(slice).as_slice();
+
+        let mut index = 0;
+        for c in char::decode_utf16(units.iter().copied()) {
+            match c {
+                Ok(c) => index += c.len_utf16(),
+                Err(_) => return Err(Utf16Error { index }),
+            }
+        }
+
+        Ok(Self::from(units))
+    }
+
     /// Creates a `WideString` with "size" amount of zeroes.
     ///
     /// If the given size is 0, then it returns from the `Default` constructor.
@@ -153,7 +419,7 @@ impl WideString {
 
     /// Creates a `WideString` containing `text` and fills the remaining `size` with zeroes.
     pub fn from_str_with_size(text: &str, size: usize) -> Self {
-        let mut vec = get_wide_string(text);
+        let mut vec = to_wide(text);
         vec.resize(size, 0);
 
         Self { bytes: vec }
@@ -172,67 +438,508 @@ impl WideString {
         }
     }
 
-    /// Adds Pushes another `WideString` to itself.
-    /// It removes the null-byte from `self` before pushing on the other one.
+    /// Pushes another `WideString` to itself. Only the code units before the null-byte
+    /// are used, so the padding of both is removed, and only one null-byte is left at the end.
     ///
-    //// If the underlying vector of "other" is empty, the function does nothing.
+    /// If `other` is empty, the function does nothing.
     pub fn push_wide(&mut self, other: &Self) {
-        let len = other.bytes.len();
-        if len > 0 {
-            self.bytes.reserve(len);
-            self.bytes.pop();
-            self.bytes.extend(other.bytes.iter());
+        let units = other.as_slice();
+        if !units.is_empty() {
+            let len = self.len();
+            self.bytes.truncate(len);
+
+            self.bytes.reserve_exact(units.len() + 1);
+            self.bytes.extend_from_slice(units);
+            self.bytes.push(0);
         }
     }
 
-    /// Adds a `&str` to itself.
+    /// Adds a `&str` to itself. It's added after the code units before the null-byte,
+    /// so padding from e.g. [`with_size`](Self::with_size) is removed first.
     ///
     /// If the text is empty, the function does nothing.
     pub fn push_str(&mut self, text: &str) {
-        use ::std::ffi::OsStr;
-        use ::std::os::windows::ffi::OsStrExt;
+        if !text.is_empty() {
+            let len = self.len();
+            self.bytes.truncate(len);
+
+            // Characters outside the BMP are two code units, and the null-byte is one more.
+            let units: usize = text.chars().map(char::len_utf16).sum();
+            self.bytes.reserve_exact(units + 1);
+            self.bytes.extend(text.encode_utf16());
+            self.bytes.push(0);
+        }
+    }
 
-        if text.len() != 0 {
-            let text_as_wide = OsStr::new(text).encode_wide().chain(std::iter::once(0));
+    /// Adds a `char` to itself. Characters outside the BMP are added as a surrogate pair.
+    /// Like [`push_str`](Self::push_str), padding is removed first.
+    pub fn push_char(&mut self, c: char) {
+        let mut units = [0_u16; 2];
 
-            self.bytes.reserve_exact(text.chars().count());
+        let len = self.len();
+        self.bytes.truncate(len);
+        self.bytes.extend_from_slice(c.encode_utf16(&mut units));
+        self.bytes.push(0);
+    }
+
+    /// Returns the index of the first code unit, where `needle` is found.
+    pub fn find(&self, needle: &str) -> Option<usize> {
+        let needle: Vec<u16> = needle.encode_utf16().collect();
+        if needle.is_empty() {
+            return Some(0);
+        }
+
+        self.as_slice()
+            .windows(needle.len())
+            .position(|window| window == &needle[..])
+    }
+
+    /// Returns a copy, where every match of `from` is replaced with `to`. The matches are found
+    /// from the start and don't overlap, just like [`str::replace`]. If `from` is empty, it's
+    /// the same as `clone` without the padding.
+    ///
+    /// ## Example
+    /// ```
+    /// use winutils::wstring::WideString;
+    ///
+    /// let path = WideString::from("C:/Windows/System32");
+    /// assert_eq!(path.replace("/", "\\"), "C:\\Windows\\System32");
+    /// ```
+    pub fn replace(&self, from: &str, to: &str) -> Self {
+        let from: Vec<u16> = from.encode_utf16().collect();
+        let units = self.as_slice();
+        if from.is_empty() {
+            return Self::from(units);
+        }
+
+        let mut bytes = Vec::with_capacity(units.len() + 1);
+        let mut i = 0;
+
+        while i < units.len() {
+            if units[i..].starts_with(&from) {
+                bytes.extend(to.encode_utf16());
+                i += from.len();
+            } else {
+                bytes.push(units[i]);
+                i += 1;
+            }
+        }
+        bytes.push(0);
+
+        Self { bytes }
+    }
+
+    /// Returns `true` if `needle` is found in the `WideString`.
+    pub fn contains(&self, needle: &str) -> bool {
+        self.find(needle).is_some()
+    }
+
+    /// Returns `true` if the `WideString` starts with `prefix`.
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        let mut units = self.iter();
+        prefix.encode_utf16().all(|c| units.next() == Some(c))
+    }
+
+    /// Returns a copy without the leading and trailing whitespace, ending with a single null-byte.
+    pub fn trim(&self) -> Self {
+        let is_whitespace = |c: &u16| char::from_u32(*c as u32).is_some_and(char::is_whitespace);
+
+        let units = self.as_slice();
+        let start = units.iter().position(|c| !is_whitespace(c)).unwrap_or(units.len());
+        let end = units.iter().rposition(|c| !is_whitespace(c)).map_or(start, |i| i + 1);
+
+        Self::from(&units[start..end])
+    }
+
+    /// Removes the trailing null-bytes except one, e.g. the padding from
+    /// [`from_str_with_size`](Self::from_str_with_size). A null-byte is added, if there's none.
+    pub fn trim_nul(&mut self) {
+        while self.bytes.ends_with(&[0, 0]) {
             self.bytes.pop();
-            self.bytes.extend(text_as_wide);
         }
+
+        if self.bytes.last() != Some(&0) {
+            self.bytes.push(0);
+        }
+    }
+
+    /// Splits the `WideString` on `sep` and returns the parts, each ending with a null-byte.
+    /// Empty parts are kept, just like [`str::split`].
+    ///
+    /// ## Example
+    /// ```
+    /// use winutils::wstring::WideString;
+    ///
+    /// let path = WideString::from("C:\\Windows;C:\\Windows\\System32");
+    /// assert_eq!(path.split(';'), [WideString::from("C:\\Windows"), WideString::from("C:\\Windows\\System32")]);
+    /// ```
+    pub fn split(&self, sep: char) -> Vec<Self> {
+        let mut buffer = [0_u16; 2];
+        let sep = &*sep.encode_utf16(&mut buffer);
+
+        let units = self.as_slice();
+        let mut parts = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+
+        while i + sep.len() <= units.len() {
+            if &units[i..i + sep.len()] == sep {
+                parts.push(Self::from(&units[start..i]));
+                i += sep.len();
+                start = i;
+            } else {
+                i += 1;
+            }
+        }
+        parts.push(Self::from(&units[start..]));
+
+        parts
+    }
+
+    /// Splits a list of null-terminated strings, that ends with an extra null-byte, like
+    /// `REG_MULTI_SZ` values and environment blocks. Unlike the other functions, this
+    /// looks at the whole buffer and not only up to the first null-byte.
+    pub fn split_nul(&self) -> Vec<Self> {
+        split_multi(&self.bytes)
+    }
+
+    /// Sets the length to `len` code units and adds the null-byte after them, like when an
+    /// API has written `len` code units to [`mut_ptr`](Self::mut_ptr) and returned the length.
+    ///
+    /// # Safety
+    /// `len` must not be larger than the capacity, and the first `len` code units must be
+    /// initialized, e.g. written by the API or from [`with_size`](Self::with_size).
+    pub unsafe fn set_len(&mut self, len: usize) {
+        self.bytes.set_len(len);
+        self.bytes.push(0);
+    }
+
+    /// Removes everything after the first null-byte, e.g. when an API has written a shorter
+    /// string to a buffer from [`with_size`](Self::with_size). A null-byte is added, if
+    /// there's none.
+    pub fn truncate_to_nul(&mut self) {
+        match self.bytes.iter().position(|&c| c == 0) {
+            Some(nul) => self.bytes.truncate(nul + 1),
+            None => self.bytes.push(0),
+        }
+    }
+
+    /// Returns a copy in uppercase, using the casing rules of Windows.
+    pub fn to_upper(&self) -> Self {
+        let mut upper = self.clone();
+        let len = upper.len() as u32;
+
+        unsafe {
+            CharUpperBuffW(upper.mut_ptr(), len);
+        }
+
+        upper
+    }
+
+    /// Returns a copy in lowercase, using the casing rules of Windows.
+    pub fn to_lower(&self) -> Self {
+        let mut lower = self.clone();
+        let len = lower.len() as u32;
+
+        unsafe {
+            CharLowerBuffW(lower.mut_ptr(), len);
+        }
+
+        lower
+    }
+
+    /// Encodes the `WideString` as a self-describing frame, for sending it over pipes
+    /// or sockets. The frame is the amount of code units as a little-endian `u32`,
+    /// followed by the UTF-16LE content without the null-byte.
+    pub fn to_wire(&self) -> Vec<u8> {
+        let units = self.as_slice();
+
+        let mut frame = Vec::with_capacity(4 + units.len() * 2);
+        frame.extend_from_slice(&(units.len() as u32).to_le_bytes());
+        for unit in units.iter() {
+            frame.extend_from_slice(&unit.to_le_bytes());
+        }
+
+        frame
+    }
+
+    /// Parses one frame made by [`to_wire`](Self::to_wire) from the start of `bytes`.
+    /// Returns the `WideString` and the amount of bytes the frame used, so multiple
+    /// frames can be read from the same buffer.
+    pub fn from_wire(bytes: &[u8]) -> io::Result<(Self, usize)> {
+        if bytes.len() < 4 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Missing frame length."));
+        }
+
+        let count = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+        let end = match count.checked_mul(2).and_then(|size| size.checked_add(4)) {
+            Some(end) if end <= bytes.len() => end,
+            _ => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Frame is incomplete.")),
+        };
+
+        let mut vec: Vec<u16> = bytes[4..end]
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        vec.push(0);
+
+        Ok((Self { bytes: vec }, end))
     }
 }
 
-impl fmt::Display for WideString {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let string = String::from_utf16_lossy(&self.bytes[..self.bytes.len() - 1]);
-        write!(f, "{}", string)
+/// The error from [`WideString::try_from_slice`], when it's not valid UTF-16.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Utf16Error {
+    index: usize,
+}
+
+impl Utf16Error {
+    /// Returns the index of the unpaired surrogate.
+    pub fn index(&self) -> usize {
+        self.index
     }
 }
 
-impl fmt::Display for WideStr {
+impl fmt::Display for Utf16Error {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unpaired surrogate at index {}.", self.index)
+    }
+}
+
+impl std::error::Error for Utf16Error {}
+
+/// A list of null-terminated strings, that ends with an extra null-byte. This is the format
+/// of e.g. environment blocks, `REG_MULTI_SZ` values and the paths for `SHFileOperationW`.
+///
+/// Empty strings are skipped when it's built, since they would end the list early.
+///
+/// ## Example
+/// ```
+/// use winutils::wstring::{MultiString, WideString};
+///
+/// let list: MultiString = ["one", "two"].into_iter().collect();
+/// assert_eq!(list.bytes, "one\0two\0\0".encode_utf16().collect::<Vec<u16>>());
+/// assert_eq!(list.to_vec(), [WideString::from("one"), WideString::from("two")]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct MultiString {
+    pub bytes: Vec<u16>,
+}
+
+impl MultiString {
+    /// Returns a raw pointer to the buffer, for passing it to the Windows API.
+    #[inline]
+    pub fn ptr(&self) -> *const u16 {
+        self.bytes.as_ptr()
+    }
+
+    /// Copies a list from a raw pointer, until the double null-byte is found.
+    ///
+    /// # Safety
+    /// `ptr` must either be null, or point to a list that ends with a double null-byte.
+    pub unsafe fn from_raw_ptr(ptr: *const u16) -> Self {
+        if ptr.is_null() {
+            return Self::default();
+        }
 
-        if self.ptr.is_null() {
-            return write!(f, "");
+        // The list ends at a null-byte right after another one, or at the start if it's empty.
+        let mut len = 0;
+        while *ptr.add(len) != 0 || (len > 0 && *ptr.add(len - 1) != 0) {
+            len += 1;
         }
+        let len = len.max(1) + 1;
 
-        unsafe {
-            let len = (0..).take_while(|&i| *self.ptr.offset(i) != 0).count() + 1;
-            let slice = std::slice::from_raw_parts(self.ptr, len);
-            
-            write!(f, "{}", String::from_utf16_lossy(&slice[..len - 1]))
+        Self {
+            bytes: std::slice::from_raw_parts(ptr, len).to_owned(),
         }
     }
+
+    /// Returns the strings in the list.
+    pub fn to_vec(&self) -> Vec<WideString> {
+        split_multi(&self.bytes)
+    }
 }
 
-impl fmt::Debug for WideStr {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        unsafe {
-            let len = (0..).take_while(|&i| *self.ptr.offset(i) != 0).count() + 1;
-            let slice = std::slice::from_raw_parts(self.ptr, len);
+/// An empty list, which is only the double null-byte.
+impl Default for MultiString {
+    fn default() -> Self {
+        Self { bytes: vec![0, 0] }
+    }
+}
+
+impl<'a> FromIterator<&'a str> for MultiString {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(iter: I) -> Self {
+        let mut bytes = Vec::new();
+
+        for text in iter.into_iter().filter(|text| !text.is_empty()) {
+            bytes.extend(to_wide_no_nul(text));
+            bytes.push(0);
+        }
+
+        if bytes.is_empty() {
+            bytes.push(0);
+        }
+        bytes.push(0);
 
-            write!(f, "WideStr({:?}) &{:?}", self.ptr, slice)
+        Self { bytes }
+    }
+}
+
+/// Splits `units` on the null-bytes, until an empty string (double null-byte) or the end.
+fn split_multi(units: &[u16]) -> Vec<WideString> {
+    units
+        .split(|&c| c == 0)
+        .take_while(|entry| !entry.is_empty())
+        .map(WideString::from)
+        .collect()
+}
+
+/// Two `WideString`'s are equal if they are equal up to the null-byte, so padding
+/// from e.g. [`from_str_with_size`](WideString::from_str_with_size) is ignored.
+/// An [`empty`](WideString::empty) `WideString` has no null-byte, so it's not equal
+/// to a `Default` one.
+impl PartialEq for WideString {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice_with_nul() == other.as_slice_with_nul()
+    }
+}
+
+impl Eq for WideString {}
+
+/// Hashes the code units up to the null-byte, so it matches [`PartialEq`].
+impl Hash for WideString {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_slice_with_nul().hash(state);
+    }
+}
+
+/// Compares the code units up to the null-byte lexicographically. This is not the
+/// same order as the Windows API uses for sorting, e.g. in Explorer.
+impl Ord for WideString {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.as_slice_with_nul().cmp(other.as_slice_with_nul())
+    }
+}
+
+impl PartialOrd for WideString {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq<str> for WideString {
+    fn eq(&self, other: &str) -> bool {
+        self.iter().eq(other.encode_utf16())
+    }
+}
+
+impl PartialEq<&str> for WideString {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+/// Returns the code units before the null-byte, the same as [`WideString::as_slice`].
+impl AsRef<[u16]> for WideString {
+    fn as_ref(&self) -> &[u16] {
+        self.as_slice()
+    }
+}
+
+impl Extend<char> for WideString {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        let mut units = [0_u16; 2];
+
+        let len = self.len();
+        self.bytes.truncate(len);
+        for c in iter {
+            self.bytes.extend_from_slice(c.encode_utf16(&mut units));
+        }
+        self.bytes.push(0);
+    }
+}
+
+impl<'a> Extend<&'a str> for WideString {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, iter: I) {
+        for text in iter {
+            self.push_str(text);
         }
     }
 }
 
+impl Add<&str> for WideString {
+    type Output = WideString;
+
+    fn add(mut self, text: &str) -> WideString {
+        self.push_str(text);
+        self
+    }
+}
+
+impl Add<&WideString> for WideString {
+    type Output = WideString;
+
+    fn add(mut self, other: &WideString) -> WideString {
+        self.push_wide(other);
+        self
+    }
+}
+
+impl AddAssign<&str> for WideString {
+    fn add_assign(&mut self, text: &str) {
+        self.push_str(text);
+    }
+}
+
+impl AddAssign<&WideString> for WideString {
+    fn add_assign(&mut self, other: &WideString) {
+        self.push_wide(other);
+    }
+}
+
+impl AddAssign<char> for WideString {
+    fn add_assign(&mut self, c: char) {
+        self.push_char(c);
+    }
+}
+
+impl fmt::Display for WideString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", String::from_utf16_lossy(self.as_slice()))
+    }
+}
+
+/// Serializes as a normal string, so it's readable in e.g. config files. Unpaired
+/// surrogates are replaced with `U+FFFD`, so they're not kept when it's deserialized again.
+#[cfg(feature = "serde")]
+impl serde::Serialize for WideString {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&String::from_utf16_lossy(self.as_slice()))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for WideString {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|text| Self::from(text.as_str()))
+    }
+}
+
+impl fmt::Display for WideStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", String::from_utf16_lossy(self.as_slice()))
+    }
+}
+
+impl fmt::Debug for WideStr<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "WideStr({:?}) &{:?}", self.ptr, self.as_bytes())
+    }
+}
+
+#[link(name = "User32")]
+extern "system" {
+    fn CharUpperBuffW(lpsz: *mut u16, cchLength: u32) -> u32;
+    fn CharLowerBuffW(lpsz: *mut u16, cchLength: u32) -> u32;
+}